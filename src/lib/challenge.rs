@@ -0,0 +1,262 @@
+use super::score::{self, Scoring};
+use super::settings::{Settings, WordSource};
+use super::WORD_LIST;
+
+const VERSION: &str = "bt1";
+const DIGITS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+// Everything that decides the text and the rules of a test, encoded as
+// `bt1-<duration>-<word list>-<seed>` followed by optional modifiers:
+// `s` for sudden death, `a<accuracy>`, `w<minimum wpm>`, `t<target wpm>` and
+// `p<scoring profile>` when it is not the classic one, then `m<minimum wpm
+// window>` and `g<grace period>` in tenths of a second when not the defaults.
+// Numbers are written in base 36 to keep codes short enough to paste in chat.
+pub struct Challenge {
+    duration: u64,
+    word_list: String,
+    seed: u64,
+    sudden_death: bool,
+    min_accuracy: Option<u64>,
+    min_wpm: Option<u64>,
+    target_wpm: Option<u64>,
+    scoring: &'static dyn Scoring,
+    min_wpm_window: u64,
+    fail_grace_period: u64,
+}
+
+impl Challenge {
+    pub fn new(settings: &Settings, seed: u64) -> Challenge {
+        Challenge {
+            duration: settings.duration.round() as u64,
+            word_list: WORD_LIST.to_string(),
+            seed,
+            sudden_death: settings.sudden_death,
+            min_accuracy: settings.min_accuracy.map(|accuracy| accuracy.round() as u64),
+            min_wpm: settings.min_wpm.map(|wpm| wpm.round() as u64),
+            target_wpm: settings.target_wpm.map(|wpm| wpm.round() as u64),
+            scoring: settings.scoring,
+            min_wpm_window: tenths(settings.min_wpm_window),
+            fail_grace_period: tenths(settings.fail_grace_period),
+        }
+    }
+
+    pub fn parse(code: &str) -> Result<Challenge, String> {
+        let invalid = || format!("invalid challenge code '{}'", code);
+        let mut parts = code.trim().split('-');
+
+        if parts.next() != Some(VERSION) {
+            return Err(invalid());
+        }
+
+        let duration = parts.next().and_then(decode).filter(|duration| *duration > 0);
+        let word_list = parts.next().map(str::to_string);
+        let seed = parts.next().and_then(decode);
+
+        let defaults = Settings::new();
+        let mut challenge = match (duration, word_list, seed) {
+            (Some(duration), Some(word_list), Some(seed)) => Challenge {
+                duration,
+                word_list,
+                seed,
+                sudden_death: false,
+                min_accuracy: None,
+                min_wpm: None,
+                target_wpm: None,
+                scoring: &score::Classic,
+                min_wpm_window: tenths(defaults.min_wpm_window),
+                fail_grace_period: tenths(defaults.fail_grace_period),
+            },
+            _ => return Err(invalid()),
+        };
+
+        if challenge.word_list != WORD_LIST {
+            return Err(format!("unknown word list '{}'", challenge.word_list));
+        }
+
+        for modifier in parts {
+            let mut chars = modifier.chars();
+            let kind = chars.next();
+            let value = chars.as_str();
+            match (kind, decode(value)) {
+                (Some('s'), None) if value.is_empty() => challenge.sudden_death = true,
+                (Some('a'), Some(accuracy)) => challenge.min_accuracy = Some(accuracy),
+                (Some('w'), Some(wpm)) => challenge.min_wpm = Some(wpm),
+                (Some('t'), Some(wpm)) => challenge.target_wpm = Some(wpm),
+                (Some('p'), _) => challenge.scoring = score::profile(value).ok_or_else(invalid)?,
+                (Some('m'), Some(window)) => challenge.min_wpm_window = window,
+                (Some('g'), Some(grace)) => challenge.fail_grace_period = grace,
+                _ => return Err(invalid()),
+            }
+        }
+
+        Ok(challenge)
+    }
+
+    // Also undoes any setting from the config file that would change the
+    // words, so the seed gives the same text for everyone
+    pub fn apply(&self, settings: &mut Settings) {
+        settings.words = WordSource::English;
+        settings.top = None;
+        settings.min_length = None;
+        settings.max_length = None;
+        settings.must_contain = None;
+        settings.no_repeats = false;
+        settings.zen = false;
+        settings.lesson = false;
+        settings.duration = self.duration as f64;
+        settings.seed = Some(self.seed);
+        settings.sudden_death = self.sudden_death;
+        settings.min_accuracy = self.min_accuracy.map(|accuracy| accuracy as f64);
+        settings.min_wpm = self.min_wpm.map(|wpm| wpm as f64);
+        settings.target_wpm = self.target_wpm.map(|wpm| wpm as f64);
+        settings.scoring = self.scoring;
+        settings.min_wpm_window = self.min_wpm_window as f64 / 10.0;
+        settings.fail_grace_period = self.fail_grace_period as f64 / 10.0;
+    }
+
+    pub fn code(&self) -> String {
+        let mut parts = vec![
+            VERSION.to_string(),
+            encode(self.duration),
+            self.word_list.clone(),
+            encode(self.seed),
+        ];
+
+        if self.sudden_death {
+            parts.push("s".to_string());
+        }
+        if let Some(accuracy) = self.min_accuracy {
+            parts.push(format!("a{}", encode(accuracy)));
+        }
+        if let Some(wpm) = self.min_wpm {
+            parts.push(format!("w{}", encode(wpm)));
+        }
+        if let Some(wpm) = self.target_wpm {
+            parts.push(format!("t{}", encode(wpm)));
+        }
+        if self.scoring.name() != score::Classic.name() {
+            parts.push(format!("p{}", self.scoring.name()));
+        }
+        let defaults = Settings::new();
+        if self.min_wpm_window != tenths(defaults.min_wpm_window) {
+            parts.push(format!("m{}", encode(self.min_wpm_window)));
+        }
+        if self.fail_grace_period != tenths(defaults.fail_grace_period) {
+            parts.push(format!("g{}", encode(self.fail_grace_period)));
+        }
+
+        parts.join("-")
+    }
+}
+
+// A typo checksum of a result for a challenge, so a posted score can be
+// checked against its code. It is an unkeyed hash anyone can compute, so it
+// catches typos and casual edits but not deliberate forgery.
+pub fn result_hash(code: &str, net_wpm: f64, accuracy: f64) -> String {
+    let text = format!("{}|{:.1}|{:.1}", code, net_wpm, accuracy);
+    // 64-bit FNV-1a, which is stable across platforms and Rust versions
+    let hash = text.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{:08x}", hash >> 32)
+}
+
+// Handles `bananatype verify <code> <net wpm> <accuracy> <hash>`
+pub fn verify<I: Iterator<Item = String>>(mut args: I) -> Result<(), String> {
+    let usage = "usage: bananatype verify <code> <net wpm> <accuracy> <checksum>";
+    let (code, net_wpm, accuracy, hash) = match (args.next(), args.next(), args.next(), args.next()) {
+        (Some(code), Some(net_wpm), Some(accuracy), Some(hash)) => (code, net_wpm, accuracy, hash),
+        _ => return Err(usage.to_string()),
+    };
+
+    Challenge::parse(&code)?;
+    let net_wpm: f64 = net_wpm.trim_end_matches('%').parse().map_err(|_| usage.to_string())?;
+    let accuracy: f64 = accuracy.trim_end_matches('%').parse().map_err(|_| usage.to_string())?;
+
+    if result_hash(&code, net_wpm, accuracy) == hash.to_lowercase() {
+        println!("valid: {:.1} WPM at {:.1}% accuracy on {}", net_wpm, accuracy, code);
+        Ok(())
+    } else {
+        Err("the checksum does not match this result".to_string())
+    }
+}
+
+fn tenths(seconds: f64) -> u64 {
+    (seconds * 10.0).round() as u64
+}
+
+fn encode(mut value: u64) -> String {
+    let mut digits = Vec::new();
+    loop {
+        digits.push(DIGITS[(value % 36) as usize]);
+        value /= 36;
+        if value == 0 {
+            break;
+        }
+    }
+    digits.iter().rev().map(|digit| *digit as char).collect()
+}
+
+fn decode(text: &str) -> Option<u64> {
+    if text.is_empty() {
+        return None;
+    }
+    u64::from_str_radix(text, 36).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_round_trip() {
+        let mut settings = Settings::new();
+        settings.duration = 60.0;
+        settings.sudden_death = true;
+        settings.min_accuracy = Some(95.0);
+        settings.target_wpm = Some(80.0);
+        settings.scoring = &score::Monkeytype;
+        settings.fail_grace_period = 2.5;
+
+        let code = Challenge::new(&settings, 123_456_789).code();
+        assert_eq!(code, "bt1-1o-english-21i3v9-s-a2n-t28-pmonkeytype-gp");
+
+        // As if set in the config file
+        let mut decoded = Settings::new();
+        decoded.words = WordSource::Quotes;
+        decoded.top = Some(200);
+        decoded.min_length = Some(3);
+        decoded.no_repeats = true;
+        decoded.zen = true;
+        decoded.lesson = true;
+        Challenge::parse(&code).unwrap().apply(&mut decoded);
+        assert!(decoded.words == WordSource::English && !decoded.filters_words());
+        assert!(!decoded.zen && !decoded.lesson);
+        assert_eq!(decoded.duration, 60.0);
+        assert_eq!(decoded.seed, Some(123_456_789));
+        assert!(decoded.sudden_death);
+        assert_eq!(decoded.min_accuracy, Some(95.0));
+        assert_eq!(decoded.min_wpm, None);
+        assert_eq!(decoded.target_wpm, Some(80.0));
+        assert_eq!(decoded.scoring.name(), "monkeytype");
+        assert_eq!(decoded.min_wpm_window, 3.0);
+        assert_eq!(decoded.fail_grace_period, 2.5);
+    }
+
+    #[test]
+    fn invalid_codes_are_rejected() {
+        for code in ["", "bt2-u-english-1", "bt1-0-english-1", "bt1-u-english", "bt1-u-english-1-x",
+                     "bt1-u-klingon-1", "bt1-u-english-1-pnone", "bt1-u-english-1-é",
+                     "bt1-u-english-1-éa"] {
+            assert!(Challenge::parse(code).is_err(), "{}", code);
+        }
+    }
+
+    #[test]
+    fn result_hashes_depend_on_the_result() {
+        let hash = result_hash("bt1-u-english-1", 72.4, 96.1);
+        assert_eq!(hash, result_hash("bt1-u-english-1", 72.4, 96.1));
+        assert_ne!(hash, result_hash("bt1-u-english-1", 72.5, 96.1));
+        assert_ne!(hash, result_hash("bt1-u-english-2", 72.4, 96.1));
+    }
+}
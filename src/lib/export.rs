@@ -0,0 +1,164 @@
+use super::report::Report;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+const SPARKLINE: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+#[derive(Clone, Copy)]
+pub enum Format {
+    Csv,
+    Json,
+    Markdown,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name {
+            "csv" => Some(Format::Csv),
+            "json" => Some(Format::Json),
+            "md" | "markdown" => Some(Format::Markdown),
+            _ => None,
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            Format::Csv => "csv",
+            Format::Json => "json",
+            Format::Markdown => "md",
+        }
+    }
+}
+
+pub fn render(report: &Report, format: Format) -> Result<String, io::Error> {
+    match format {
+        Format::Csv => Ok(csv(report)),
+        Format::Json => Ok(serde_json::to_string_pretty(report)?),
+        Format::Markdown => Ok(markdown(report)),
+    }
+}
+
+// Writes the report in every format next to each other, returning the shared file stem
+pub fn write_all(report: &Report, dir: &Path) -> Result<PathBuf, io::Error> {
+    let stem = dir.join(format!("bananatype-{}", report.timestamp));
+    for format in [Format::Csv, Format::Json, Format::Markdown] {
+        fs::write(stem.with_extension(format.extension()), render(report, format)?)?;
+    }
+    Ok(stem)
+}
+
+// Handles `bananatype export [--format csv|json|markdown] [--output <file>]`
+pub fn run<I: Iterator<Item = String>>(mut args: I) -> Result<(), String> {
+    let mut format = Format::Json;
+    let mut output = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => {
+                let name = args.next().ok_or("missing value for '--format'")?;
+                format = Format::from_name(&name)
+                    .ok_or(format!("unknown export format '{}'", name))?;
+            }
+            "--output" => output = Some(args.next().ok_or("missing value for '--output'")?),
+            _ => return Err(format!("unknown argument '{}'", arg)),
+        }
+    }
+
+    let report = Report::load_last()
+        .map_err(|error| format!("could not load the last result: {}", error))?;
+    let contents = render(&report, format).map_err(|error| error.to_string())?;
+
+    match output {
+        Some(path) => fs::write(&path, contents)
+            .map_err(|error| format!("could not write '{}': {}", path, error)),
+        None => io::stdout()
+            .write_all(contents.as_bytes())
+            .map_err(|error| error.to_string()),
+    }
+}
+
+fn csv(report: &Report) -> String {
+    let mut lines = vec![
+        "metric,value".to_string(),
+        format!("timestamp,{}", report.timestamp),
+        format!("date,{}", report.date()),
+        format!("mode,{}", report.mode),
+        format!("word_list,{}", report.word_list),
+        format!("seed,{}", report.seed.map_or(String::new(), |seed| seed.to_string())),
+        format!("challenge,{}", report.challenge.as_deref().unwrap_or("")),
+        format!("result_hash,{}", report.result_hash.as_deref().unwrap_or("")),
+        format!("duration,{:.3}", report.duration),
+        format!("gross_wpm,{:.2}", report.gross_wpm),
+        format!("net_wpm,{:.2}", report.net_wpm),
+        format!("accuracy,{:.2}", report.accuracy),
+        format!("consistency,{:.2}", report.consistency),
+        format!("peak_burst,{:.2}", report.peak_burst),
+        format!("correct,{}", report.correct),
+        format!("incorrect,{}", report.incorrect),
+        format!("extra,{}", report.extra),
+        format!("missed,{}", report.missed),
+        format!("failure,{}", report.failure.as_deref().unwrap_or("")),
+        format!("invalid,{}", report.invalid.as_deref().unwrap_or("")),
+        format!("pauses,{}", report.pauses),
+        String::new(),
+        "time,net_wpm,gross_wpm".to_string(),
+    ];
+
+    for i in 0..report.log.time.len() {
+        lines.push(format!("{:.3},{:.2},{:.2}",
+                           report.log.time[i], report.log.net_wpm[i], report.log.gross_wpm[i]));
+    }
+
+    lines.join("\n") + "\n"
+}
+
+fn markdown(report: &Report) -> String {
+    let mut lines = vec![
+        format!("## Bananatype result ({})", report.date()),
+        String::new(),
+        "| Mode | Net WPM | Gross WPM | Accuracy | Consistency | Peak burst | Duration |".to_string(),
+        "| --- | --- | --- | --- | --- | --- | --- |".to_string(),
+        format!("| {} | {:.1} | {:.1} | {:.1}% | {:.1}% | {:.1} | {:.2}s |",
+                report.mode, report.net_wpm, report.gross_wpm, report.accuracy,
+                report.consistency, report.peak_burst, report.duration),
+        String::new(),
+        format!("Characters: {} correct, {} incorrect, {} extra, {} missed",
+                report.correct, report.incorrect, report.extra, report.missed),
+        String::new(),
+        format!("Net WPM over time: `{}`", sparkline(&report.log.net_wpm)),
+    ];
+
+    if let (Some(code), Some(hash)) = (&report.challenge, &report.result_hash) {
+        lines.extend(vec![String::new(), format!("Challenge: `{}` (typo checksum `{}`)", code, hash)]);
+    }
+
+    if let Some(failure) = &report.failure {
+        lines.extend(vec![String::new(), format!("**Failed:** {}", failure)]);
+    }
+
+    if let Some(invalid) = &report.invalid {
+        lines.extend(vec![String::new(), format!("**Invalid:** {}", invalid)]);
+    }
+
+    if report.pauses > 0 {
+        lines.extend(vec![String::new(), format!("**Paused:** {}", report.pauses)]);
+    }
+
+    lines.join("\n") + "\n"
+}
+
+pub fn sparkline(values: &[f64]) -> String {
+    let max = values.iter().cloned().fold(0.0, f64::max);
+    values
+        .iter()
+        .map(|value| {
+            if max == 0.0 {
+                SPARKLINE[0]
+            } else {
+                let level = (value / max * (SPARKLINE.len() - 1) as f64).round() as usize;
+                SPARKLINE[level.min(SPARKLINE.len() - 1)]
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,28 @@
+use super::report::{data_dir, Report};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+
+const HISTORY_FILE: &str = "history.jsonl";
+
+// One report per line so appending never rewrites earlier results
+pub fn append(report: &Report) -> Result<(), io::Error> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(data_dir()?.join(HISTORY_FILE))?;
+    writeln!(file, "{}", serde_json::to_string(report)?)
+}
+
+pub fn load() -> Result<Vec<Report>, io::Error> {
+    let contents = match fs::read_to_string(data_dir()?.join(HISTORY_FILE)) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(error),
+    };
+
+    // Lines that fail to parse are skipped rather than losing the whole history
+    Ok(contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
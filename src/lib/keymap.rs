@@ -0,0 +1,250 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::fmt;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Action {
+    Restart,
+    NextTest,
+    RepeatTest,
+    PracticeMissed,
+    Quit,
+    OpenMenu,
+    ToggleStats,
+    Export,
+    Help,
+    Review,
+    Finish,
+    Pause,
+    DeleteWord,
+    DeleteLine,
+}
+
+impl Action {
+    pub const ALL: [Action; 14] = [
+        Action::Restart,
+        Action::NextTest,
+        Action::RepeatTest,
+        Action::PracticeMissed,
+        Action::Quit,
+        Action::OpenMenu,
+        Action::ToggleStats,
+        Action::Export,
+        Action::Help,
+        Action::Review,
+        Action::Finish,
+        Action::Pause,
+        Action::DeleteWord,
+        Action::DeleteLine,
+    ];
+
+    // The name used for the action in the config file
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Restart => "restart",
+            Action::NextTest => "next-test",
+            Action::RepeatTest => "repeat-test",
+            Action::PracticeMissed => "practice-missed",
+            Action::Quit => "quit",
+            Action::OpenMenu => "menu",
+            Action::ToggleStats => "stats",
+            Action::Export => "export",
+            Action::Help => "help",
+            Action::Review => "review",
+            Action::Finish => "finish",
+            Action::Pause => "pause",
+            Action::DeleteWord => "delete-word",
+            Action::DeleteLine => "delete-line",
+        }
+    }
+
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Action::Restart => "Restart with new words",
+            Action::NextTest => "Start the next test",
+            Action::RepeatTest => "Repeat the same words",
+            Action::PracticeMissed => "Practice the missed words",
+            Action::Quit => "End the test or quit",
+            Action::OpenMenu => "Choose the test duration",
+            Action::ToggleStats => "Show statistics",
+            Action::Export => "Export the result",
+            Action::Help => "Show this help",
+            Action::Review => "Compare the input with the text",
+            Action::Finish => "Finish a zen mode test (quits any other test)",
+            Action::Pause => "Pause the test",
+            Action::DeleteWord => "Delete the current word",
+            Action::DeleteLine => "Delete the current line",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.iter().cloned().find(|action| action.name() == name)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    pub fn from_event(event: KeyEvent) -> KeyBinding {
+        match (event.code, event.modifiers) {
+            // Most terminals send Ctrl+Backspace as Ctrl+H
+            (KeyCode::Char('h'), modifiers) if modifiers.contains(KeyModifiers::CONTROL) => {
+                KeyBinding { code: KeyCode::Backspace, modifiers }
+            }
+            // The case of a character already says whether shift was held
+            (KeyCode::Char(c), modifiers) => KeyBinding {
+                code: KeyCode::Char(c),
+                modifiers: modifiers - KeyModifiers::SHIFT,
+            },
+            (code, modifiers) => KeyBinding { code, modifiers },
+        }
+    }
+
+    // Parses bindings such as "tab", "ctrl+backspace", "alt+w" or "R"
+    pub fn parse(text: &str) -> Option<KeyBinding> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut parts: Vec<&str> = text.trim().split('+').collect();
+        let key = parts.pop()?;
+
+        for modifier in parts {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return None,
+            };
+        }
+
+        let code = match key.to_lowercase().as_str() {
+            "tab" => KeyCode::Tab,
+            "esc" | "escape" => KeyCode::Esc,
+            "enter" | "return" => KeyCode::Enter,
+            "backspace" => KeyCode::Backspace,
+            "space" => KeyCode::Char(' '),
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            name if name.len() > 1 && name.starts_with('f') => {
+                KeyCode::F(name[1..].parse().ok()?)
+            }
+            _ => {
+                let mut chars = key.chars();
+                let c = chars.next()?;
+                if chars.next().is_some() {
+                    return None;
+                }
+                if modifiers.contains(KeyModifiers::SHIFT) {
+                    KeyCode::Char(c.to_ascii_uppercase())
+                } else {
+                    KeyCode::Char(c)
+                }
+            }
+        };
+
+        Some(KeyBinding::from_event(KeyEvent::new(code, modifiers)))
+    }
+
+    // Plain characters are typed during a test instead of triggering actions
+    pub fn is_text(&self) -> bool {
+        matches!(self.code, KeyCode::Char(_))
+            && !self.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "shift+")?;
+        }
+        match self.code {
+            KeyCode::Tab => write!(f, "tab"),
+            KeyCode::Esc => write!(f, "esc"),
+            KeyCode::Enter => write!(f, "enter"),
+            KeyCode::Backspace => write!(f, "backspace"),
+            KeyCode::Up => write!(f, "up"),
+            KeyCode::Down => write!(f, "down"),
+            KeyCode::Left => write!(f, "left"),
+            KeyCode::Right => write!(f, "right"),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            _ => write!(f, "?"),
+        }
+    }
+}
+
+pub struct Keymap {
+    bindings: Vec<(Action, KeyBinding)>,
+}
+
+impl Keymap {
+    pub fn new() -> Keymap {
+        let defaults = [
+            (Action::Restart, "tab"),
+            (Action::NextTest, "r"),
+            (Action::RepeatTest, "ctrl+r"),
+            (Action::RepeatTest, "R"),
+            (Action::PracticeMissed, "p"),
+            (Action::Quit, "esc"),
+            (Action::Quit, "q"),
+            (Action::OpenMenu, "m"),
+            (Action::ToggleStats, "s"),
+            (Action::Export, "e"),
+            (Action::Help, "?"),
+            (Action::Help, "f1"),
+            (Action::Review, "v"),
+            (Action::Finish, "ctrl+d"),
+            (Action::Pause, "ctrl+p"),
+            (Action::DeleteWord, "ctrl+backspace"),
+            (Action::DeleteWord, "alt+backspace"),
+            (Action::DeleteWord, "ctrl+w"),
+            (Action::DeleteLine, "ctrl+u"),
+        ];
+
+        Keymap {
+            bindings: defaults
+                .iter()
+                .filter_map(|(action, key)| KeyBinding::parse(key).map(|key| (*action, key)))
+                .collect(),
+        }
+    }
+
+    // Replaces every default binding of the action
+    pub fn bind(&mut self, action: Action, keys: Vec<KeyBinding>) {
+        self.bindings.retain(|(bound, _)| *bound != action);
+        self.bindings.extend(keys.into_iter().map(|key| (action, key)));
+    }
+
+    pub fn action(&self, key: KeyBinding) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(_, bound)| *bound == key)
+            .map(|(action, _)| *action)
+    }
+
+    pub fn key(&self, action: Action) -> String {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| *bound == action)
+            .map_or("unbound".to_string(), |(_, key)| key.to_string())
+    }
+
+    pub fn keys(&self, action: Action) -> String {
+        self.bindings
+            .iter()
+            .filter(|(bound, _)| *bound == action)
+            .map(|(_, key)| key.to_string())
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
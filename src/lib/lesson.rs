@@ -0,0 +1,136 @@
+use super::markov::{Constraints, Markov};
+use super::report::data_dir;
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+
+const PROGRESS_FILE: &str = "lessons.json";
+// Home row first, then the top row and the bottom row, strongest fingers first
+const LETTER_ORDER: &str = "fjdkslaghrueiwotypqnmvcbxz";
+const STARTING_LETTERS: usize = 4;
+// Pseudo-words drawn from the Markov model for each lesson, and how many of
+// them must differ for the lesson to use them rather than random letters
+const VOCABULARY_ATTEMPTS: usize = 200;
+const MIN_VOCABULARY: usize = 20;
+
+// Each lesson unlocks one more letter than the one before
+pub struct Lesson {
+    pub number: usize,
+    pub passed: Option<bool>,
+}
+
+impl Lesson {
+    pub fn new(number: usize) -> Lesson {
+        Lesson {
+            number: number.clamp(1, Lesson::count()),
+            passed: None,
+        }
+    }
+
+    pub fn count() -> usize {
+        LETTER_ORDER.len() - STARTING_LETTERS + 1
+    }
+
+    pub fn is_last(&self) -> bool {
+        self.number == Lesson::count()
+    }
+
+    pub fn letters(&self) -> &'static str {
+        &LETTER_ORDER[..STARTING_LETTERS + self.number - 1]
+    }
+
+    // The letter the next lesson adds
+    pub fn next_letter(&self) -> Option<char> {
+        LETTER_ORDER.chars().nth(STARTING_LETTERS + self.number - 1)
+    }
+
+    // Pseudo-words made only of unlocked letters, with the newest letter in
+    // about half of them so it gets the most practice. Once the Markov model
+    // can spell enough different words with these letters they come from it,
+    // so they are easier to pronounce; before that they are random letters.
+    pub fn words<R: Rng>(&self, rng: &mut R, count: usize, markov: &Markov) -> Vec<String> {
+        let letters: Vec<char> = self.letters().chars().collect();
+        let newest = letters[letters.len() - 1];
+        let constraints = Constraints {
+            min_length: 2,
+            max_length: 6,
+            letters: Some(self.letters()),
+        };
+
+        let vocabulary: Vec<String> = (0..VOCABULARY_ATTEMPTS)
+            .map_while(|_| markov.generate(rng, &constraints))
+            .collect::<BTreeSet<String>>()
+            .into_iter()
+            .collect();
+        let with_newest: Vec<&String> = vocabulary.iter().filter(|word| word.contains(newest)).collect();
+
+        (0..count)
+            .map(|_| {
+                let emphasize = rng.gen_bool(0.5);
+                if vocabulary.len() >= MIN_VOCABULARY {
+                    return match with_newest.choose(rng) {
+                        Some(word) if emphasize => word.to_string(),
+                        _ => vocabulary[rng.gen_range(0..vocabulary.len())].clone(),
+                    };
+                }
+
+                let length = rng.gen_range(2..=6);
+                let mut word: Vec<char> = (0..length)
+                    .map(|_| letters[rng.gen_range(0..letters.len())])
+                    .collect();
+                if emphasize {
+                    word[rng.gen_range(0..length)] = newest;
+                }
+                word.into_iter().collect()
+            })
+            .collect()
+    }
+}
+
+// The lesson to take next, kept in the data directory of the user
+#[derive(Serialize, Deserialize)]
+pub struct Progress {
+    pub lesson: usize,
+}
+
+impl Progress {
+    pub fn load() -> Progress {
+        data_dir()
+            .and_then(|dir| fs::read_to_string(dir.join(PROGRESS_FILE)))
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or(Progress { lesson: 1 })
+    }
+
+    pub fn save(&self) -> Result<(), io::Error> {
+        fs::write(data_dir()?.join(PROGRESS_FILE), serde_json::to_string(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn lessons_unlock_one_letter_each() {
+        assert_eq!(Lesson::new(1).letters(), "fjdk");
+        assert_eq!(Lesson::new(1).next_letter(), Some('s'));
+        assert_eq!(Lesson::new(4).letters(), "fjdksla");
+        assert_eq!(Lesson::new(Lesson::count()).letters().len(), 26);
+        assert_eq!(Lesson::new(Lesson::count()).next_letter(), None);
+        assert_eq!(Lesson::new(100).number, Lesson::count());
+    }
+
+    #[test]
+    fn words_only_use_unlocked_letters() {
+        let lesson = Lesson::new(3);
+        let markov = Markov::train(include_str!("words.txt").lines(), 2);
+        let words = lesson.words(&mut ChaCha8Rng::seed_from_u64(1), 200, &markov);
+        assert!(words.iter().all(|word| word.chars().all(|c| lesson.letters().contains(c))));
+        assert!(words.iter().any(|word| word.contains('l')));
+    }
+}
@@ -0,0 +1,149 @@
+use rand::Rng;
+use std::collections::HashMap;
+use std::iter;
+
+const START: char = '^';
+const END: char = '$';
+// Constraints can rule out every path through the model, so give up after a while
+const ATTEMPTS: usize = 100;
+
+pub struct Constraints<'a> {
+    pub min_length: usize,
+    pub max_length: usize,
+    // Only these letters may be used, when given
+    pub letters: Option<&'a str>,
+}
+
+// Predicts each letter of a word from the `order` letters before it, learned
+// from a list of real words, to make up new words that are easy to pronounce
+pub struct Markov {
+    order: usize,
+    transitions: HashMap<String, Vec<(char, u32)>>,
+}
+
+impl Markov {
+    pub fn train<'a, I: IntoIterator<Item = &'a str>>(words: I, order: usize) -> Markov {
+        let mut transitions: HashMap<String, Vec<(char, u32)>> = HashMap::new();
+
+        for word in words {
+            let word = word.trim().to_lowercase();
+            if word.is_empty() || !word.chars().all(char::is_alphabetic) {
+                continue;
+            }
+
+            let padded: Vec<char> = iter::repeat_n(START, order)
+                .chain(word.chars())
+                .chain(iter::once(END))
+                .collect();
+
+            for window in padded.windows(order + 1) {
+                let options = transitions.entry(window[..order].iter().collect()).or_default();
+                match options.iter_mut().find(|(next, _)| *next == window[order]) {
+                    Some((_, count)) => *count += 1,
+                    None => options.push((window[order], 1)),
+                }
+            }
+        }
+
+        Markov { order, transitions }
+    }
+
+    pub fn generate<R: Rng>(&self, rng: &mut R, constraints: &Constraints) -> Option<String> {
+        (0..ATTEMPTS).find_map(|_| self.attempt(rng, constraints))
+    }
+
+    fn attempt<R: Rng>(&self, rng: &mut R, constraints: &Constraints) -> Option<String> {
+        let mut context: Vec<char> = iter::repeat_n(START, self.order).collect();
+        let mut word = String::new();
+        let mut length = 0;
+
+        loop {
+            let options: Vec<(char, u32)> = self
+                .transitions
+                .get(&context.iter().collect::<String>())?
+                .iter()
+                .cloned()
+                .filter(|(next, _)| match *next {
+                    END => length >= constraints.min_length,
+                    next => length < constraints.max_length
+                        && constraints.letters.is_none_or(|letters| letters.contains(next)),
+                })
+                .collect();
+
+            let total: u32 = options.iter().map(|(_, count)| count).sum();
+            if total == 0 {
+                return None;
+            }
+
+            let mut pick = rng.gen_range(0..total);
+            let next = options
+                .iter()
+                .find(|(_, count)| {
+                    if pick < *count {
+                        true
+                    } else {
+                        pick -= count;
+                        false
+                    }
+                })
+                .map(|(next, _)| *next)?;
+
+            if next == END {
+                return Some(word);
+            }
+            word.push(next);
+            length += 1;
+            context.remove(0);
+            context.push(next);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    const WORDS: &str = include_str!("words.txt");
+
+    #[test]
+    fn words_follow_the_constraints() {
+        let markov = Markov::train(WORDS.lines(), 2);
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        let constraints = Constraints {
+            min_length: 4,
+            max_length: 7,
+            letters: Some("etaoinshrd"),
+        };
+
+        for _ in 0..50 {
+            let word = markov.generate(&mut rng, &constraints).unwrap();
+            assert!((4..=7).contains(&word.len()), "{}", word);
+            assert!(word.chars().all(|c| "etaoinshrd".contains(c)), "{}", word);
+        }
+    }
+
+    #[test]
+    fn impossible_constraints_give_up() {
+        let markov = Markov::train(["abc", "abd"], 1);
+        let constraints = Constraints {
+            min_length: 1,
+            max_length: 5,
+            letters: Some("xyz"),
+        };
+        assert_eq!(markov.generate(&mut ChaCha8Rng::seed_from_u64(1), &constraints), None);
+    }
+
+    #[test]
+    fn only_learned_transitions_are_used() {
+        let markov = Markov::train(["abc"], 1);
+        let constraints = Constraints {
+            min_length: 1,
+            max_length: 10,
+            letters: None,
+        };
+        assert_eq!(markov.generate(&mut ChaCha8Rng::seed_from_u64(3), &constraints),
+                   Some("abc".to_string()));
+    }
+}
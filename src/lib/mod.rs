@@ -1,26 +1,60 @@
+use crossterm::cursor;
 use crossterm::event::{poll, read, Event, KeyCode};
-use rand::{distributions::Uniform, Rng};
-use std::io::{self, BufRead, Stdout};
-use std::sync::mpsc;
-use std::thread;
-use std::time::Duration;
-use termion::raw::{IntoRawMode, RawTerminal};
-use tui::backend::CrosstermBackend;
-use tui::layout::{Alignment, Constraint, Direction, Layout};
+use crossterm::execute;
+use crossterm::style::Print;
+use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
+use rand::{seq::SliceRandom, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::BTreeSet;
+use challenge::Challenge;
+use keymap::{Action, KeyBinding};
+use settings::Caret;
+use std::env;
+use std::io::{self, Stdout};
+use std::panic;
+use std::time::{Duration, Instant};
+use tui::backend::{Backend, CrosstermBackend};
+use tui::layout::{Alignment, Constraint, Direction, Layout, Rect};
 use tui::style::{Color, Modifier, Style};
 use tui::text::{Span, Spans, Text};
 use tui::symbols;
-use tui::widgets::{Block, BorderType, Borders, Gauge, Paragraph, Wrap, Dataset, Chart, Axis, GraphType};
+use tui::widgets::{Block, BorderType, Borders, Clear, Gauge, Paragraph, Wrap, Dataset, Chart, Axis, GraphType};
 use tui::Terminal;
 use std::iter;
 
+mod challenge;
+mod export;
+mod history;
+mod keymap;
+mod lesson;
+mod markov;
+mod ngrams;
+mod report;
+mod review;
 mod score;
+mod settings;
+mod source;
+mod stats;
 
-type CrosstermTerminal = Terminal<CrosstermBackend<RawTerminal<Stdout>>>;
+pub use challenge::verify;
+pub use export::run as export;
+pub use settings::Settings;
+
+type CrosstermTerminal = Terminal<CrosstermBackend<Stdout>>;
 
 const NUMBER_OF_WORDS: usize = 100;
-const TEST_DURATION: f64 = 30.0;
-const TIMER_REFRESH_RATE: f64 = 2.0;
+const WORD_LIST: &str = "english";
+const DURATIONS: [f64; 4] = [15.0, 30.0, 60.0, 120.0];
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+const LOG_INTERVAL: f64 = 1.0;
+const RESULTS_COLUMN_WIDTH: u16 = 28;
+const ERRORS_AXIS_WIDTH: u16 = 8;
+const NGRAMS_WIDTH: u16 = 24;
+const SLOWEST_NGRAMS: usize = 10;
+// Across all tests an n-gram needs a few samples before its average means much
+const NGRAM_HISTORY_MIN_COUNT: u32 = 3;
+const AFK_THRESHOLD: f64 = 5.0;
+#[allow(dead_code)]
 const BANANATYPE: &str = r"
  /$$                                                           /$$                                  
 | $$                                                          | $$                                  
@@ -36,7 +70,6 @@ const BANANATYPE: &str = r"
 
 ";
 
-// TODO: draw line graph for raw wpm and net wpm
 // TODO: separate styling from mechanics of the test
 struct Theme {
     fg: Color,
@@ -60,12 +93,61 @@ impl Theme {
     }
 }
 
-struct Settings {}
+#[derive(Clone, Copy)]
+enum Failure {
+    SuddenDeath,
+    Accuracy(f64),
+    Pace(f64, f64),
+}
+
+impl Failure {
+    fn describe(&self) -> String {
+        match self {
+            Failure::SuddenDeath => "sudden death: a mistake was made".to_string(),
+            Failure::Accuracy(threshold) => {
+                format!("accuracy dropped below {:.1}%", threshold)
+            }
+            Failure::Pace(target, window) => {
+                format!("net WPM stayed below {:.1} for {:.0} seconds", target, window)
+            }
+        }
+    }
+}
+
+// Invalid results are shown but never saved or counted toward personal bests
+#[derive(Clone, Copy)]
+enum Invalid {
+    NothingTyped,
+    Afk(f64),
+    Quit,
+}
+
+impl Invalid {
+    fn describe(&self) -> String {
+        match self {
+            Invalid::NothingTyped => "no characters were typed".to_string(),
+            Invalid::Afk(idle_seconds) => {
+                format!("AFK detected ({:.1} seconds idle)", idle_seconds)
+            }
+            Invalid::Quit => "the test was quit early".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Overlay {
+    Help,
+    Menu(usize),
+    Review,
+    Paused,
+}
 
 struct Log {
     time: Vec<f64>,
     net_wpm: Vec<f64>,
     gross_wpm: Vec<f64>,
+    keystrokes: Vec<f64>,
+    errors: Vec<f64>,
 }
 
 impl Log {
@@ -74,11 +156,102 @@ impl Log {
             time: Vec::new(),
             net_wpm: Vec::new(),
             gross_wpm: Vec::new(),
+            keystrokes: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    fn keystroke(&mut self, time: f64, correct: bool) {
+        self.keystrokes.push(time);
+        if !correct {
+            self.errors.push(time);
+        }
+    }
+
+    // Counts events in each one-second window, the last one possibly partial
+    fn per_second(events: &[f64], duration: f64) -> Vec<f64> {
+        let windows = duration.ceil() as usize;
+        let mut counts = vec![0.0; windows];
+        for time in events {
+            if windows > 0 {
+                counts[(time.floor() as usize).min(windows - 1)] += 1.0;
+            }
+        }
+        counts
+    }
+
+    // Each window is plotted at the moment it closes
+    fn window_end(second: usize, duration: f64) -> f64 {
+        (second as f64 + 1.0).min(duration)
+    }
+
+    fn per_second_wpm(&self, duration: f64) -> Vec<f64> {
+        Log::per_second(&self.keystrokes, duration)
+            .iter()
+            .enumerate()
+            .map(|(second, count)| {
+                let window = (duration - second as f64).min(1.0);
+                (count / 5.0) * (60.0 / window)
+            })
+            .collect()
+    }
+
+    // Speeds of the whole seconds only. A last partial second holds too few
+    // keystrokes to scale up to a minute, and a test that ends on a keystroke
+    // always has one.
+    fn full_second_wpm(&self, duration: f64) -> Vec<f64> {
+        let mut wpm = self.per_second_wpm(duration);
+        wpm.truncate(duration.floor() as usize);
+        wpm
+    }
+
+    fn errors_per_second(&self, duration: f64) -> Vec<(f64, f64)> {
+        Log::per_second(&self.errors, duration)
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0.0)
+            .map(|(second, count)| (Log::window_end(second, duration), *count))
+            .collect()
+    }
+
+    fn raw_wpm_per_second(&self, duration: f64) -> Vec<(f64, f64)> {
+        self.full_second_wpm(duration)
+            .into_iter()
+            .enumerate()
+            .map(|(second, wpm)| (Log::window_end(second, duration), wpm))
+            .collect()
+    }
+
+    // 100% means every second was typed at the same speed
+    fn consistency(&self, duration: f64) -> f64 {
+        let wpm = self.full_second_wpm(duration);
+        if wpm.is_empty() {
+            return 0.0;
+        }
+
+        let mean = wpm.iter().sum::<f64>() / wpm.len() as f64;
+        if mean == 0.0 {
+            return 0.0;
+        }
+
+        let variance = wpm.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / wpm.len() as f64;
+        let coefficient_of_variation = variance.sqrt() / mean;
+        (100.0 * (1.0 - coefficient_of_variation)).max(0.0)
+    }
+
+    fn peak_burst(&self, duration: f64) -> f64 {
+        self.full_second_wpm(duration).into_iter().fold(0.0, f64::max)
+    }
+
+    fn next_sample(&self) -> f64 {
+        match self.time.last() {
+            Some(time) => (time / LOG_INTERVAL).floor() * LOG_INTERVAL + LOG_INTERVAL,
+            None => 0.0,
         }
     }
 
     fn update(&mut self, time: f64, net_wpm: f64, gross_wpm: f64) {
-        if self.time.len() == 0 || self.time[self.time.len() - 1] < time {
+        if self.time.is_empty() || self.time[self.time.len() - 1] < time {
             self.time.push(time);
             self.net_wpm.push(net_wpm);
             self.gross_wpm.push(gross_wpm);
@@ -86,73 +259,180 @@ impl Log {
     }
 }
 
-pub struct TypingTest<'a> {
+pub struct TypingTest<'a, B: Backend = CrosstermBackend<Stdout>> {
+    words: Vec<String>,
+    seed: Option<u64>,
     text: Vec<Span<'a>>,
+    typed: Vec<char>,
+    mistakes: BTreeSet<usize>,
     position: usize,
-    terminal: CrosstermTerminal,
+    terminal: Terminal<B>,
+    start_time: Option<Instant>,
     elapsed_seconds: f64,
     score: score::Score,
     theme: Theme,
     log: Log,
+    settings: Settings,
+    failure: Option<Failure>,
+    below_pace_since: Option<f64>,
+    invalid: Option<Invalid>,
+    last_keystroke: f64,
+    report: Option<report::Report>,
+    ngram_timer: ngrams::Timer,
+    lesson: Option<lesson::Lesson>,
+    text_width: usize,
+    paused: bool,
+    pauses: u32,
 }
 
 impl TypingTest<'_> {
-    pub fn new() -> TypingTest<'static> {
-        //let text = TypingTest::generate_text();
+    pub fn new(settings: Settings) -> TypingTest<'static> {
         let terminal = TypingTest::setup_terminal().unwrap();
+        let typing_test = TypingTest::with_terminal(settings, terminal);
+        let _ = typing_test.set_cursor_shape();
+        typing_test
+    }
+
+    fn setup_terminal() -> Result<CrosstermTerminal, io::Error> {
+        // Restore the terminal before the panic message is printed, otherwise
+        // it is lost on the alternate screen and the shell is left in raw mode
+        let default_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let _ = TypingTest::restore_terminal();
+            default_hook(info);
+        }));
+
+        terminal::enable_raw_mode()?;
+        let mut stdout = io::stdout();
+        execute!(stdout, EnterAlternateScreen, cursor::Hide)?;
+        let backend = CrosstermBackend::new(stdout);
+        let mut terminal = Terminal::new(backend)?;
+        terminal.clear()?;
+        Ok(terminal)
+    }
+
+    fn set_cursor_shape(&self) -> Result<(), io::Error> {
+        if self.settings.caret != Caret::Bar {
+            return Ok(());
+        }
+
+        let mut stdout = io::stdout();
+        execute!(stdout, cursor::SetCursorShape(cursor::CursorShape::Line))?;
+        if self.settings.caret_blink {
+            execute!(stdout, cursor::EnableBlinking)
+        } else {
+            execute!(stdout, cursor::DisableBlinking)
+        }
+    }
+
+    fn restore_terminal() -> Result<(), io::Error> {
+        terminal::disable_raw_mode()?;
+        // Puts the cursor back to the shape and blinking the user configured
+        execute!(io::stdout(), LeaveAlternateScreen, Print("\x1b[0 q"), cursor::Show)?;
+        Ok(())
+    }
+}
 
+impl<B: Backend> TypingTest<'_, B> {
+    fn with_terminal(settings: Settings, terminal: Terminal<B>) -> TypingTest<'static, B> {
         let mut typing_test = TypingTest {
+                             words: Vec::new(),
+                             seed: None,
                              text: Vec::new(),
+                             typed: Vec::new(),
+                             mistakes: BTreeSet::new(),
                              terminal,
                              position: 0,
+                             start_time: None,
                              elapsed_seconds: 0.0,
-                             score: score::Score::new(),
+                             score: score::Score::new(settings.scoring),
                              theme: Theme::new(),
-                             log: Log::new()
+                             log: Log::new(),
+                             settings,
+                             failure: None,
+                             below_pace_since: None,
+                             invalid: None,
+                             last_keystroke: 0.0,
+                             report: None,
+                             ngram_timer: ngrams::Timer::new(),
+                             lesson: None,
+                             text_width: 0,
+                             paused: false,
+                             pauses: 0,
                          };
 
+        if typing_test.settings.lesson {
+            typing_test.lesson = Some(lesson::Lesson::new(lesson::Progress::load().lesson));
+        }
         typing_test.generate_text();
         typing_test
     }
 
     fn generate_text(&mut self) {
-        let file = include_str!("words.txt");
-        let file: Vec<String> = file
-            .lines()
-            .map(|line| line.to_string())
-            .collect();
+        // A seed given on the command line only applies to the first test
+        let seed = self.settings.seed.take().unwrap_or_else(|| rand::random::<u32>() as u64);
+        self.seed = Some(seed);
+
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        self.words = match &self.lesson {
+            // Zen mode has no text until it is typed
+            _ if self.settings.zen => Vec::new(),
+            Some(lesson) => lesson.words(&mut rng, NUMBER_OF_WORDS, &source::markov(&self.settings)),
+            None => source::from_settings(&self.settings).text(&mut rng, NUMBER_OF_WORDS),
+        };
 
-        let rand_nums: Vec<usize> = rand::thread_rng()
-            .sample_iter(Uniform::from(0..file.len()))
-            .take(NUMBER_OF_WORDS)
-            .collect();
+        self.build_text();
+    }
 
+    fn build_text(&mut self) {
         let mut spans = Vec::new();
 
-        for num in rand_nums {
+        for word in &self.words {
             spans.extend([
-                Span::styled(file[num].clone(),
+                Span::styled(word.clone(),
                              Style::default().fg(self.theme.fg)),
                 Span::styled(" ", Style::default().fg(self.theme.fg)),
             ]);
         }
 
+        // In zen mode the typed characters are the text, followed by a blank
+        // for the caret
+        if self.settings.zen {
+            spans = vec![Span::styled(" ", Style::default().fg(self.theme.fg))];
+        }
+
         self.text = spans;
     }
 
     fn update_char(&mut self, character: char) {
-        let current_word = &self.text[self.position].content;
-        let next_word = &self.text[self.position + 1].content;
+        if self.settings.zen {
+            self.append_char(character);
+            return;
+        }
+
+        // Nothing is left to type once the cursor is past the last character
+        let current_word = match self.text.get(self.position) {
+            Some(span) => &span.content,
+            None => return,
+        };
+        let next_word = self.text.get(self.position + 1).map_or("", |span| &span.content);
 
         let (former, latter) = current_word.split_at(1);
         let former = former.chars().next().unwrap();
 
+        self.typed.push(character);
+        self.log.keystroke(self.elapsed_seconds, former == character);
+        self.ngram_timer.keystroke(character, self.elapsed_seconds, former == character);
+        if former != character {
+            self.mistakes.insert(self.position);
+        }
+
         let former = match former {
             former if former == character => {
                 self.score.calculate_correct();
                 Span::styled(former.to_string(), Style::default().fg(self.theme.correct))
             }
-            former if former == ' ' => {
+            ' ' => {
                 self.score.calculate_incorrect();
                 Span::styled(' '.to_string(), Style::default().bg(self.theme.incorrect))
             }
@@ -166,7 +446,7 @@ impl TypingTest<'_> {
             let (cursor, latter) = latter.split_at(1);
 
             let cursor = Span::styled(cursor.to_string(),
-                                      Style::default().fg(self.theme.cursor).bg(self.theme.fg));
+                                      Style::default().fg(self.theme.fg));
 
             let latter = Span::styled(latter.to_string(),
                                       Style::default().fg(self.theme.fg));
@@ -175,7 +455,7 @@ impl TypingTest<'_> {
                 .splice(self.position..self.position + 1, [former, cursor, latter]);
         } else if latter.len() == 1 {
             let cursor = Span::styled(latter.to_string(),
-                                      Style::default().fg(self.theme.cursor).bg(self.theme.fg));
+                                      Style::default().fg(self.theme.fg));
 
             self.text
                 .splice(self.position..self.position + 1, [former, cursor]);
@@ -183,7 +463,7 @@ impl TypingTest<'_> {
             let (cursor, latter) = next_word.split_at(1);
 
             let cursor = Span::styled(cursor.to_string(),
-                                      Style::default().fg(self.theme.cursor).bg(self.theme.fg));
+                                      Style::default().fg(self.theme.fg));
 
             let latter = Span::styled(latter.to_string(),
                                       Style::default().fg(self.theme.fg));
@@ -192,17 +472,42 @@ impl TypingTest<'_> {
                 .splice(self.position..self.position + 2, [former, cursor, latter]);
         } else if next_word.len() == 1 {
             let cursor = Span::styled(next_word.to_string(),
-                                      Style::default().fg(self.theme.cursor).bg(self.theme.fg));
+                                      Style::default().fg(self.theme.fg));
 
             self.text
                 .splice(self.position..self.position + 2, [former, cursor]);
+        } else {
+            // The last character of the text
+            self.text[self.position] = former;
         }
 
         self.position += 1;
-        self.refresh();
+        let _ = self.refresh();
+    }
+
+    // Zen mode has nothing to compare against, so every character is correct
+    // and becomes part of the text
+    fn append_char(&mut self, character: char) {
+        self.typed.push(character);
+        self.log.keystroke(self.elapsed_seconds, true);
+        self.ngram_timer.keystroke(character, self.elapsed_seconds, true);
+        self.score.calculate_correct();
+        self.text.insert(self.position,
+                         Span::styled(character.to_string(), Style::default().fg(self.theme.correct)));
+        self.position += 1;
+    }
+
+    // Words typed so far, for zen mode where there is no text to count
+    fn typed_words(&self) -> Vec<String> {
+        self.typed.iter().collect::<String>().split_whitespace().map(str::to_string).collect()
     }
 
     fn backspace(&mut self) {
+        self.delete_char();
+        let _ = self.refresh();
+    }
+
+    fn delete_char(&mut self) {
         if self.position > 0 {
             if (self.text[self.position - 1].content == " " && self.text[self.position - 1].style.bg == Some(self.theme.bg))
                 || self.text[self.position - 1].style.fg == Some(self.theme.correct) {
@@ -210,21 +515,150 @@ impl TypingTest<'_> {
             } else {
                 self.score.calculate_incorrect_backspace();
             }
+            self.typed.pop();
+            self.ngram_timer.interrupt();
+            if let Some(span) = self.text.get_mut(self.position) {
+                span.style = Style::default().fg(self.theme.fg).bg(self.theme.bg);
+            }
+            self.position -= 1;
             self.text[self.position].style = Style::default().fg(self.theme.fg).bg(self.theme.bg);
-            self.position = self.position - 1;
-            self.text[self.position].style = Style::default().fg(self.theme.cursor).bg(self.theme.fg);
+            if self.settings.zen {
+                self.text.remove(self.position);
+            }
+        }
+    }
+
+    // Typed characters each have their own span, so the target character
+    // before the cursor is the content of the previous span
+    fn previous_is_space(&self) -> bool {
+        self.position > 0 && self.text[self.position - 1].content == " "
+    }
+
+    // Deletes back to the start of the current word, or of the previous word
+    // when the cursor is already at the start of one
+    fn delete_word(&mut self) {
+        while self.previous_is_space() {
+            self.delete_char();
         }
-        self.refresh();
+        while self.position > 0 && !self.previous_is_space() {
+            self.delete_char();
+        }
+        let _ = self.refresh();
+    }
+
+    fn delete_line(&mut self) {
+        let line_start = self
+            .line_starts()
+            .into_iter()
+            .rev()
+            .find(|start| *start < self.position)
+            .unwrap_or(0);
+        while self.position > line_start {
+            self.delete_char();
+        }
+        let _ = self.refresh();
+    }
+
+    // Offsets of the first character of each line when the words are wrapped
+    // to the width of the text box
+    fn line_starts(&self) -> Vec<usize> {
+        let mut starts = vec![0];
+        let mut offset = 0;
+        let mut line_length = 0;
+
+        // Zen mode has no words until they are typed. The text is the input
+        // followed by a blank, so each typed word is followed by one space too.
+        let typed: String = self.typed.iter().collect();
+        let words: Vec<&str> = if self.settings.zen {
+            typed.split(' ').collect()
+        } else {
+            self.words.iter().map(String::as_str).collect()
+        };
+
+        for word in words {
+            let length = word.chars().count();
+            // The space after the word is on the same line
+            if line_length > 0 && line_length + length + 1 > self.text_width {
+                starts.push(offset);
+                line_length = 0;
+            }
+            line_length += length + 1;
+            offset += length + 1;
+        }
+
+        starts
+    }
+
+    // The wrapped lines around the cursor, keeping its line in the middle
+    // once the first lines have been typed, and the cursor's row and column
+    // within them
+    fn viewport(&self) -> (Vec<Spans<'static>>, (usize, usize)) {
+        let starts = self.line_starts();
+        let pace = self.pace_position();
+        let mut lines: Vec<Vec<Span<'static>>> = vec![Vec::new(); starts.len()];
+        let mut line = 0;
+        let mut offset = 0;
+
+        for span in &self.text {
+            for c in span.content.chars() {
+                while line + 1 < starts.len() && starts[line + 1] <= offset {
+                    line += 1;
+                }
+
+                let style = if offset == self.position {
+                    self.caret_style(span.style)
+                } else if Some(offset) == pace {
+                    span.style.bg(self.theme.highlight)
+                } else {
+                    span.style
+                };
+
+                // Runs of the same style are merged back into one span
+                match lines[line].last_mut() {
+                    Some(last) if last.style == style => last.content.to_mut().push(c),
+                    _ => lines[line].push(Span::styled(c.to_string(), style)),
+                }
+                offset += 1;
+            }
+        }
+
+        let cursor_line = starts.iter().rposition(|start| *start <= self.position).unwrap_or(0);
+        let visible = self.settings.visible_lines;
+        let first = cursor_line
+            .saturating_sub(visible / 2)
+            .min(lines.len().saturating_sub(visible));
+        let cursor = (cursor_line - first, self.position - starts[cursor_line]);
+
+        let lines = lines.into_iter().skip(first).take(visible).map(Spans::from).collect();
+        (lines, cursor)
+    }
+
+    fn caret_style(&self, style: Style) -> Style {
+        let style = match self.settings.caret {
+            Caret::Block => Style::default().fg(self.theme.cursor).bg(self.theme.fg),
+            Caret::Underline => style.fg(self.theme.cursor).add_modifier(Modifier::UNDERLINED),
+            // The bar is the terminal's own cursor, placed in `refresh`
+            Caret::Bar | Caret::Off => return style,
+        };
+
+        if self.settings.caret_blink {
+            style.add_modifier(Modifier::SLOW_BLINK)
+        } else {
+            style
+        }
+    }
+
+    // Where someone typing at the pace caret's speed would be by now
+    fn pace_position(&self) -> Option<usize> {
+        let wpm = self.settings.pace_caret.filter(|_| !self.settings.zen)?;
+        self.start_time?;
+        Some((wpm * 5.0 / 60.0 * self.elapsed_seconds) as usize)
     }
 
     fn refresh(&mut self) -> Result<(), io::Error> {
         let net_wpm = self.score.calculate_net_wpm(self.elapsed_seconds);
         let gross_wpm = self.score.calculate_gross_wpm(self.elapsed_seconds);
 
-        if self.elapsed_seconds % ((1.0 / TIMER_REFRESH_RATE) * 2.0) == 0.0 {
-            self.log.update(self.elapsed_seconds, net_wpm, gross_wpm);
-        }
-
         let time_block = Block::default()
             .title(Span::styled(
                 "Time",
@@ -234,14 +668,16 @@ impl TypingTest<'_> {
             .borders(Borders::ALL)
             .border_type(BorderType::Thick);
 
+        // Zen mode counts up, since there is no time limit to count down to
+        let (ratio, remaining) = match self.time_limit() {
+            Some(limit) => (self.elapsed_seconds / limit, limit - self.elapsed_seconds),
+            None => (0.0, self.elapsed_seconds),
+        };
         let progress = Gauge::default()
             .block(time_block)
             .gauge_style(Style::default().fg(self.theme.fg))
-            .ratio(self.elapsed_seconds / TEST_DURATION)
-            .label(format!(
-                "{}",
-                TEST_DURATION.round() as u8 - (self.elapsed_seconds).round() as u8
-            ));
+            .ratio(ratio)
+            .label(format!("{}", remaining.ceil() as u32));
 
         let gross_wpm_block = Block::default()
             .title(Span::styled(
@@ -288,9 +724,15 @@ impl TypingTest<'_> {
         .alignment(Alignment::Center)
         .wrap(Wrap { trim: true });
 
+        let (third_title, third_value) = if self.settings.zen {
+            ("Words", self.typed_words().len().to_string())
+        } else {
+            ("Accuracy", format!("{:.1}", self.score.calculate_accuracy()))
+        };
+
         let accuracy_block = Block::default()
             .title(Span::styled(
-                "Accuracy",
+                third_title,
                 Style::default()
                     .fg(self.theme.fg)
                     .add_modifier(Modifier::BOLD),
@@ -300,7 +742,7 @@ impl TypingTest<'_> {
             .border_type(BorderType::Thick);
 
         let accuracy = Paragraph::new(Span::styled(
-            format!("{:.1}", self.score.calculate_accuracy()),
+            third_value,
             Style::default()
                 .fg(self.theme.fg)
                 .add_modifier(Modifier::BOLD),
@@ -320,9 +762,26 @@ impl TypingTest<'_> {
             .borders(Borders::ALL)
             .border_type(BorderType::Thick);
 
-        let text = Paragraph::new(Spans::from(self.text.clone()))
-            .block(input_block)
-            .wrap(Wrap { trim: true });
+        // Borders take one column on each side
+        self.text_width = self.terminal.size()?.width.saturating_sub(2) as usize;
+        let visible_lines = self.settings.visible_lines;
+
+        // The text is hidden while paused so it cannot be read ahead
+        let (lines, (cursor_row, cursor_column)) = if self.paused {
+            (Vec::new(), (0, 0))
+        } else {
+            self.viewport()
+        };
+        let text = Paragraph::new(Text::from(lines))
+            .block(input_block);
+        let bar = self.settings.caret == Caret::Bar && !self.paused;
+
+        let popup = Some(Overlay::Paused).filter(|_| self.paused).map(|overlay| {
+            let (title, lines) = self.overlay_text(overlay);
+            let width = lines.iter().map(|line| line.width()).max().unwrap_or(0) as u16 + 4;
+            let height = lines.len() as u16 + 2;
+            (Paragraph::new(Text::from(lines)).block(self.results_block(title)), width, height)
+        });
 
         self.terminal.draw(|frame| {
             let size = frame.size();
@@ -332,7 +791,8 @@ impl TypingTest<'_> {
                     [
                         Constraint::Length(3),
                         Constraint::Length(3),
-                        Constraint::Min(8),
+                        Constraint::Length(visible_lines as u16 + 2),
+                        Constraint::Min(0),
                     ]
                     .as_ref(),
                 )
@@ -365,107 +825,698 @@ impl TypingTest<'_> {
             frame.render_widget(net_wpm, live_stats_layout[1]);
             frame.render_widget(accuracy, live_stats_layout[2]);
             frame.render_widget(text, text_layout[0]);
-            //frame.render_widget(text, layout[0]);
+
+            // Without a cursor position the terminal cursor stays hidden
+            if bar {
+                let area = text_layout[0];
+                frame.set_cursor(area.x + 1 + cursor_column as u16, area.y + 1 + cursor_row as u16);
+            }
+
+            if let Some((popup, width, height)) = popup {
+                let area = Self::centered(width, height, text_layout[0]);
+                frame.render_widget(Clear, area);
+                frame.render_widget(popup, area);
+            }
         })?;
 
         Ok(())
     }
 
-    fn setup_terminal() -> Result<CrosstermTerminal, io::Error> {
-        let stdout = io::stdout().into_raw_mode()?;
-        let backend = CrosstermBackend::new(stdout);
-        let mut terminal = Terminal::new(backend)?;
-        terminal.clear()?;
-        Ok(terminal)
+    fn cleanup_terminal(&mut self) -> Result<(), io::Error> {
+        self.terminal.clear()?;
+        self.terminal.set_cursor(0, 0)
+    }
+
+    // Texts that run out, like custom texts, end the test once the last word
+    // is typed, without waiting for the trailing space
+    fn finished_text(&self) -> bool {
+        !self.settings.zen && self.position + 1 >= self.text.len()
+    }
+
+    // Zen mode goes on until it is finished by hand
+    fn time_limit(&self) -> Option<f64> {
+        Some(self.settings.duration).filter(|_| !self.settings.zen)
+    }
+
+    fn out_of_time(&self) -> bool {
+        self.time_limit().is_some_and(|limit| self.elapsed_seconds >= limit)
     }
 
-    fn cleanup_terminal(&mut self) {
-        self.terminal.clear();
-        self.terminal.set_cursor(0, 0);
+    // The time the results charts cover
+    fn chart_duration(&self) -> f64 {
+        self.time_limit().unwrap_or(self.elapsed_seconds).max(1.0)
     }
 
-    fn start_timer() -> mpsc::Receiver<()> {
-        let (tx, rx) = mpsc::channel();
-        thread::spawn(move || {
-            for _ in 1..=(TEST_DURATION * TIMER_REFRESH_RATE).round() as u64 {
-                tx.send(()).unwrap();
-                thread::sleep(Duration::from_millis(
-                    ((1.0 / TIMER_REFRESH_RATE) * 1000.0).round() as u64,
-                ));
+    // Stops the clock and hides the text until any key is pressed. The time
+    // spent paused is skipped, as if the test had started that much later.
+    fn pause(&mut self) -> Result<(), io::Error> {
+        let paused_at = Instant::now();
+        self.paused = true;
+        self.pauses += 1;
+        self.ngram_timer.interrupt();
+        self.refresh()?;
+
+        loop {
+            match read()? {
+                Event::Key(_) => break,
+                Event::Resize(_, _) => self.refresh()?,
+                _ => (),
             }
-        });
-        rx
+        }
+
+        self.resume(paused_at.elapsed());
+        self.refresh()
+    }
+
+    // Moves the start of the test forward by the time spent paused, so the
+    // pause is left out of the elapsed time
+    fn resume(&mut self, paused_for: Duration) {
+        self.paused = false;
+        self.start_time = self.start_time.map(|start_time| start_time + paused_for);
+    }
+
+    // Only keys that change the input start the clock, so pausing before the
+    // first keystroke does not count as typing time
+    fn starts_clock(action: Option<Action>, key: KeyBinding, code: KeyCode) -> bool {
+        match action {
+            Some(Action::DeleteWord) | Some(Action::DeleteLine) => true,
+            Some(_) => false,
+            None => key.is_text() || code == KeyCode::Backspace,
+        }
+    }
+
+    fn update_elapsed(&mut self) {
+        if let Some(start_time) = self.start_time {
+            let elapsed = start_time.elapsed().as_secs_f64();
+            self.elapsed_seconds = self.time_limit().map_or(elapsed, |limit| elapsed.min(limit));
+        }
+    }
+
+    // Samples the score at every whole log interval that has passed
+    fn sample(&mut self) {
+        let mut time = self.log.next_sample();
+        while time <= self.elapsed_seconds {
+            self.log.update(time,
+                            self.score.calculate_net_wpm(time),
+                            self.score.calculate_gross_wpm(time));
+            time = self.log.next_sample();
+        }
+    }
+
+    fn poll_timeout(&self, last_redraw: Instant) -> Duration {
+        let redraw = REDRAW_INTERVAL.saturating_sub(last_redraw.elapsed());
+        match (self.start_time, self.time_limit()) {
+            (Some(start_time), Some(limit)) => redraw.min(
+                Duration::from_secs_f64(limit).saturating_sub(start_time.elapsed())),
+            _ => redraw,
+        }
     }
 
     fn reset(&mut self) {
+        // A passed lesson moves on to the next one
+        if self.lesson.is_some() {
+            self.lesson = Some(lesson::Lesson::new(lesson::Progress::load().lesson));
+        }
         self.generate_text();
-        self.score = score::Score::new();
+        self.clear_progress();
+    }
+
+    // Starts over on the same words
+    fn repeat(&mut self) {
+        self.build_text();
+        self.clear_progress();
+    }
+
+    // Words with a mistake at any point, even if it was corrected later
+    fn missed_words(&self) -> Vec<String> {
+        self.missed_word_counts().into_iter().map(|(word, _)| word).collect()
+    }
+
+    // How many times each missed word was missed, most missed first
+    fn missed_word_counts(&self) -> Vec<(String, usize)> {
+        let mut counts: Vec<(String, usize)> = Vec::new();
+        let mut offset = 0;
+
+        for word in &self.words {
+            let end = offset + word.chars().count() + 1;
+            if self.mistakes.range(offset..end).next().is_some() {
+                match counts.iter_mut().find(|(missed, _)| missed == word) {
+                    Some((_, count)) => *count += 1,
+                    None => counts.push((word.clone(), 1)),
+                }
+            }
+            offset = end;
+        }
+
+        counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        counts
+    }
+
+    // Starts a new test made only of the missed words, returning false if
+    // there were none
+    fn practice_missed(&mut self) -> bool {
+        let missed = self.missed_words();
+        if missed.is_empty() {
+            return false;
+        }
+
+        let mut rng = rand::thread_rng();
+        self.words = (0..NUMBER_OF_WORDS)
+            .filter_map(|_| missed.choose(&mut rng).cloned())
+            .collect();
+        // The text depends on the last test, so no seed can reproduce it
+        self.seed = None;
+        self.build_text();
+        self.clear_progress();
+        true
+    }
+
+    fn clear_progress(&mut self) {
+        self.score = score::Score::new(self.settings.scoring);
         self.log = Log::new();
+        self.typed.clear();
+        self.mistakes.clear();
         self.position = 0;
+        self.start_time = None;
         self.elapsed_seconds = 0.0;
+        self.failure = None;
+        self.below_pace_since = None;
+        self.invalid = None;
+        self.last_keystroke = 0.0;
+        self.report = None;
+        self.ngram_timer = ngrams::Timer::new();
+        self.paused = false;
+        self.pauses = 0;
+        if let Some(lesson) = &mut self.lesson {
+            lesson.passed = None;
+        }
     }
 
-    fn show_results(&mut self) -> Result<(), io::Error> {
-        let results_block = Block::default()
+    fn track_activity(&mut self) {
+        let idle_seconds = self.elapsed_seconds - self.last_keystroke;
+        if idle_seconds >= AFK_THRESHOLD {
+            self.score.calculate_idle(idle_seconds);
+        }
+        self.last_keystroke = self.elapsed_seconds;
+    }
+
+    fn validate(&mut self, quit: bool) {
+        if self.settings.zen {
+            self.words = self.typed_words();
+        }
+        self.track_activity();
+        self.sample();
+        self.log.update(self.elapsed_seconds,
+                        self.score.calculate_net_wpm(self.elapsed_seconds),
+                        self.score.calculate_gross_wpm(self.elapsed_seconds));
+        self.judge(quit);
+
+        if let Some(lesson) = &self.lesson {
+            if lesson.passed == Some(true) && !lesson.is_last() {
+                let _ = lesson::Progress { lesson: lesson.number + 1 }.save();
+            }
+        }
+
+        let report = self.build_report();
+        // The last result is kept so it can be exported after the program exits
+        let _ = report.save_last();
+        if self.invalid.is_none() {
+            // Failed runs stop early, so they are left out of the history and
+            // personal bests
+            if self.failure.is_none() {
+                let _ = history::append(&report);
+            }
+            let _ = ngrams::Ngrams::load().and_then(|mut total| {
+                total.merge(&self.ngram_timer.ngrams);
+                total.save()
+            });
+        }
+        self.report = Some(report);
+    }
+
+    // Decides whether the result counts and whether it passed the lesson
+    fn judge(&mut self, quit: bool) {
+        self.invalid = if self.score.total_characters() == 0.0 {
+            Some(Invalid::NothingTyped)
+        } else if quit && !self.settings.zen {
+            // Quitting is how zen mode ends, anywhere else the test was cut short
+            Some(Invalid::Quit)
+        } else if self.score.idle_seconds() > 0.0 {
+            Some(Invalid::Afk(self.score.idle_seconds()))
+        } else {
+            None
+        };
+
+        if let Some(lesson) = &mut self.lesson {
+            lesson.passed = Some(self.invalid.is_none()
+                && self.failure.is_none()
+                && self.score.calculate_net_wpm(self.elapsed_seconds) >= self.settings.lesson_wpm
+                && self.score.calculate_accuracy() >= self.settings.lesson_accuracy);
+        }
+    }
+
+    // Whether an action ends the test early. The finish key only ends zen
+    // mode normally, anywhere else it cuts the test short like quitting.
+    fn quits(&self, action: Action) -> bool {
+        action == Action::Quit || (action == Action::Finish && !self.settings.zen)
+    }
+
+    fn build_report(&self) -> report::Report {
+        let breakdown = score::CharacterBreakdown::new(
+            self.text.iter().flat_map(|span| span.content.chars()),
+            &self.typed,
+        );
+
+        let net_wpm = self.score.calculate_net_wpm(self.elapsed_seconds);
+        let accuracy = self.score.calculate_accuracy();
+        // Challenges only cover the whole English word list
+        let challenge = self
+            .seed
+            .filter(|_| self.word_list() == WORD_LIST && !self.settings.filters_words())
+            .map(|seed| Challenge::new(&self.settings, seed).code());
+
+        report::Report {
+            timestamp: report::Report::now(),
+            mode: self.mode(),
+            word_list: self.word_list().to_string(),
+            seed: self.seed,
+            result_hash: challenge.as_ref().map(|code| challenge::result_hash(code, net_wpm, accuracy)),
+            challenge,
+            duration: self.elapsed_seconds,
+            gross_wpm: self.score.calculate_gross_wpm(self.elapsed_seconds),
+            net_wpm,
+            accuracy,
+            consistency: self.log.consistency(self.elapsed_seconds),
+            peak_burst: self.log.peak_burst(self.elapsed_seconds),
+            correct: breakdown.correct,
+            incorrect: breakdown.incorrect,
+            extra: breakdown.extra,
+            missed: breakdown.missed,
+            failure: self.failure.map(|failure| failure.describe()),
+            invalid: self.invalid.map(|invalid| invalid.describe()),
+            pauses: self.pauses,
+            log: report::Series {
+                time: self.log.time.clone(),
+                net_wpm: self.log.net_wpm.clone(),
+                gross_wpm: self.log.gross_wpm.clone(),
+            },
+        }
+    }
+
+    fn check_fail_conditions(&mut self) -> Option<Failure> {
+        if self.settings.sudden_death && self.score.total_incorrect_characters() > 0.0 {
+            return Some(Failure::SuddenDeath);
+        }
+
+        if self.elapsed_seconds < self.settings.fail_grace_period {
+            return None;
+        }
+
+        if let Some(threshold) = self.settings.min_accuracy {
+            if self.score.calculate_accuracy() < threshold {
+                return Some(Failure::Accuracy(threshold));
+            }
+        }
+
+        if let Some(target) = self.settings.min_wpm {
+            if self.score.calculate_net_wpm(self.elapsed_seconds) < target {
+                let since = *self.below_pace_since.get_or_insert(self.elapsed_seconds);
+                if self.elapsed_seconds - since >= self.settings.min_wpm_window {
+                    return Some(Failure::Pace(target, self.settings.min_wpm_window));
+                }
+            } else {
+                self.below_pace_since = None;
+            }
+        }
+
+        None
+    }
+
+    fn mode(&self) -> String {
+        match &self.lesson {
+            _ if self.settings.zen => "zen".to_string(),
+            Some(lesson) => format!("lesson {}", lesson.number),
+            None => format!("time {:.0}s", self.settings.duration),
+        }
+    }
+
+    fn word_list(&self) -> &'static str {
+        match self.lesson {
+            _ if self.settings.zen => "zen",
+            Some(_) => "lesson",
+            None => self.settings.words.name(),
+        }
+    }
+
+    fn results_block(&self, title: &'static str) -> Block<'static> {
+        Block::default()
             .title(Span::styled(
-                "Your Results",
+                title,
                 Style::default()
                     .fg(self.theme.highlight)
                     .add_modifier(Modifier::BOLD),
             ))
             .border_style(Style::default().fg(self.theme.highlight))
             .borders(Borders::ALL)
-            .border_type(BorderType::Thick);
+            .border_type(BorderType::Thick)
+    }
+
+    fn stat(label: &str, value: String) -> Spans<'static> {
+        Spans::from(vec![
+            Span::raw(format!("{}: ", label)),
+            Span::styled(value, Style::default().add_modifier(Modifier::BOLD)),
+        ])
+    }
+
+    fn key_hint(&self, action: Action) -> Span<'static> {
+        Span::styled(self.settings.keymap.key(action),
+                     Style::default().add_modifier(Modifier::BOLD))
+    }
+
+    fn overlay_text(&self, overlay: Overlay) -> (&'static str, Vec<Spans<'static>>) {
+        match overlay {
+            Overlay::Help => {
+                let mut lines: Vec<Spans> = Action::ALL
+                    .iter()
+                    .map(|action| Self::stat(action.describe(),
+                                                   self.settings.keymap.keys(*action)))
+                    .collect();
+                lines.extend(vec![
+                    Spans::default(),
+                    Spans::from("Keys without ctrl or alt are typed during a test."),
+                    Spans::from("Press any key to close."),
+                ]);
+                ("Help", lines)
+            }
+            Overlay::Menu(selected) => {
+                let mut lines: Vec<Spans> = DURATIONS
+                    .iter()
+                    .enumerate()
+                    .map(|(i, duration)| {
+                        let style = if i == selected {
+                            Style::default().fg(self.theme.highlight).add_modifier(Modifier::BOLD)
+                        } else {
+                            Style::default().fg(self.theme.fg)
+                        };
+                        Spans::from(Span::styled(format!("{:>4.0} seconds", duration), style))
+                    })
+                    .collect();
+                lines.extend(vec![
+                    Spans::default(),
+                    Spans::from("Use up and down, then enter to start."),
+                ]);
+                ("Test Duration", lines)
+            }
+            Overlay::Review => {
+                let width = self.terminal.size().map_or(80, |size| size.width);
+                ("Review", self.review_lines(width.saturating_sub(8) as usize))
+            }
+            Overlay::Paused => ("Paused", vec![
+                Spans::from("The clock is stopped."),
+                Spans::from("Press any key to resume."),
+            ]),
+        }
+    }
+
+    // The most missed words, then the text with the input aligned under it,
+    // wrapped by word
+    fn review_lines(&self, width: usize) -> Vec<Spans<'static>> {
+        let text = Style::default();
+        let pending = Style::default().fg(self.theme.fg);
+        let correct = Style::default().fg(self.theme.correct);
+        let substituted = Style::default().fg(self.theme.incorrect).add_modifier(Modifier::BOLD);
+        let inserted = Style::default().bg(self.theme.incorrect);
+
+        let cell = |edit: review::Edit| match edit {
+            review::Edit::Match(c) => ((c, text), (c, correct)),
+            // A space typed in place of a letter would not show up otherwise
+            review::Edit::Substitution(expected, ' ') => ((expected, text), ('_', substituted)),
+            review::Edit::Substitution(expected, actual) => ((expected, text), (actual, substituted)),
+            review::Edit::Insertion(c) => ((' ', text), (c, inserted)),
+            review::Edit::Pending(c) => ((c, pending), (' ', text)),
+        };
 
-        let results = Paragraph::new(Text::from(vec![
+        // As many of the most missed words as fit on one line
+        let mut missed = String::new();
+        for (word, count) in self.missed_word_counts().iter().take(10) {
+            let entry = format!("{} ({})", word, count);
+            let joined = if missed.is_empty() { entry } else { format!("{}, {}", missed, entry) };
+            if !missed.is_empty() && "Most missed: ".len() + joined.chars().count() > width {
+                break;
+            }
+            missed = joined;
+        }
+
+        let mut lines = vec![
+            Self::stat("Most missed", if missed.is_empty() { "none".to_string() } else { missed }),
             Spans::from(vec![
-                Span::raw("Gross WPM: "),
+                Span::styled("x", substituted),
+                Span::raw(" substituted  "),
+                Span::styled("_", substituted),
+                Span::raw(" space for a letter  "),
+                Span::styled("x", inserted),
+                Span::raw(" inserted. Press any key to close."),
+            ]),
+            Spans::default(),
+        ];
+
+        let mut target_line = Vec::new();
+        let mut typed_line = Vec::new();
+
+        for edits in review::align_words(&self.words, &self.typed) {
+            if !target_line.is_empty() && target_line.len() + edits.len() > width {
+                lines.push(Spans::from(target_line.split_off(0)));
+                lines.push(Spans::from(typed_line.split_off(0)));
+                lines.push(Spans::default());
+            }
+            for edit in edits {
+                let ((expected, expected_style), (actual, actual_style)) = cell(edit);
+                target_line.push(Span::styled(expected.to_string(), expected_style));
+                typed_line.push(Span::styled(actual.to_string(), actual_style));
+            }
+            target_line.push(Span::raw(" "));
+            typed_line.push(Span::raw(" "));
+        }
+        lines.push(Spans::from(target_line));
+        lines.push(Spans::from(typed_line));
+
+        lines
+    }
+
+    fn ngram_lines(&self, ngrams: &ngrams::Ngrams, min_count: u32) -> Vec<Spans<'static>> {
+        let slowest = ngrams.slowest(SLOWEST_NGRAMS, min_count);
+        if slowest.is_empty() {
+            return vec![Spans::from(Span::styled("Not enough keystrokes",
+                                                 Style::default().fg(self.theme.fg)))];
+        }
+
+        slowest
+            .iter()
+            .map(|(ngram, timing)| {
+                Spans::from(vec![
+                    Span::styled(format!("{:<4}", ngram), Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(format!("{:>5.0} ms", timing.average() * 1000.0)),
+                    Span::styled(format!(" x{}", timing.count), Style::default().fg(self.theme.fg)),
+                ])
+            })
+            .collect()
+    }
+
+    // A rectangle of the given size in the middle of the area, clipped to fit
+    fn centered(width: u16, height: u16, area: Rect) -> Rect {
+        let width = width.min(area.width);
+        let height = height.min(area.height);
+        Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        }
+    }
+
+    fn draw_results(&mut self, status: Option<&str>, overlay: Option<Overlay>) -> Result<(), io::Error> {
+        let title = match self.failure {
+            Some(_) => "Test Failed",
+            None => "Your Results",
+        };
+
+        let mut summary = Vec::new();
+
+        if let Some(failure) = self.failure {
+            summary.push(Spans::from(vec![
+                Span::raw("Failed: "),
                 Span::styled(
-                    format!(
-                        "{:.1}",
-                        self.score.calculate_gross_wpm(self.elapsed_seconds)
-                    ),
-                    Style::default().add_modifier(Modifier::BOLD),
+                    failure.describe(),
+                    Style::default()
+                        .fg(self.theme.incorrect)
+                        .add_modifier(Modifier::BOLD),
                 ),
-            ]),
-            Spans::from(vec![
-                Span::raw("Net WPM: "),
+            ]));
+        }
+
+        if let Some(invalid) = self.invalid {
+            summary.push(Spans::from(vec![
+                Span::raw("Invalid: "),
                 Span::styled(
-                    format!(
-                        "{:.1}",
-                        self.score.calculate_net_wpm(self.elapsed_seconds)
-                    ),
-                    Style::default().add_modifier(Modifier::BOLD),
+                    invalid.describe(),
+                    Style::default()
+                        .fg(self.theme.incorrect)
+                        .add_modifier(Modifier::BOLD),
                 ),
-            ]),
-            Spans::from(vec![
-                Span::raw("Accuracy: "),
+            ]));
+        }
+
+        if self.pauses > 0 {
+            summary.push(Spans::from(vec![
+                Span::raw("Paused: "),
                 Span::styled(
-                    format!(
-                        "{:.1}",
-                        self.score.calculate_accuracy()
-                    ),
-                    Style::default().add_modifier(Modifier::BOLD),
+                    format!("{} (the clock stopped while paused)", self.pauses),
+                    Style::default().fg(self.theme.highlight),
                 ),
-            ]),
+            ]));
+        }
+
+        if let Some(lesson) = &self.lesson {
+            let outcome = match (lesson.passed, lesson.next_letter()) {
+                (Some(true), Some(letter)) => format!("passed, '{}' is unlocked", letter),
+                (Some(true), None) => "passed, every letter is unlocked".to_string(),
+                _ => format!("not passed, it needs {:.0} WPM at {:.0}% accuracy",
+                             self.settings.lesson_wpm, self.settings.lesson_accuracy),
+            };
+            let color = match lesson.passed {
+                Some(true) => self.theme.correct,
+                _ => self.theme.incorrect,
+            };
+            summary.push(Spans::from(vec![
+                Span::raw(format!("Lesson {} of {} ({}): ", lesson.number, lesson::Lesson::count(),
+                                  lesson.letters())),
+                Span::styled(outcome, Style::default().fg(color).add_modifier(Modifier::BOLD)),
+            ]));
+        }
+
+        if let Some(report) = &self.report {
+            if let Some(target) = self.settings.target_wpm {
+                let (outcome, color) = if report.net_wpm >= target {
+                    ("reached", self.theme.correct)
+                } else {
+                    ("missed", self.theme.incorrect)
+                };
+                summary.push(Spans::from(vec![
+                    Span::raw(format!("Target {:.0} WPM: ", target)),
+                    Span::styled(outcome, Style::default().fg(color).add_modifier(Modifier::BOLD)),
+                ]));
+            }
+
+            let scoring = self.score.scoring();
+            summary.push(Spans::from(vec![
+                Span::raw("Scoring: "),
+                Span::styled(scoring.name(), Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(format!(" ({})", scoring.formula())),
+            ]));
+
+            if let (Some(code), Some(hash)) = (&report.challenge, &report.result_hash) {
+                summary.push(Spans::from(vec![
+                    Span::raw("Challenge: "),
+                    Span::styled(code.clone(), Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw("  Typo checksum: "),
+                    Span::styled(hash.clone(), Style::default().add_modifier(Modifier::BOLD)),
+                ]));
+            }
+        }
+
+        summary.extend(vec![
             Spans::from(vec![
                 Span::raw("Press "),
-                Span::styled("r", Style::default().add_modifier(Modifier::BOLD)),
-                Span::raw(" to restart or "),
-                Span::styled("q", Style::default().add_modifier(Modifier::BOLD)),
+                self.key_hint(Action::NextTest),
+                Span::raw(" for the next test, "),
+                self.key_hint(Action::RepeatTest),
+                Span::raw(" to repeat it, "),
+                self.key_hint(Action::PracticeMissed),
+                Span::raw(" to practice missed words, "),
+                self.key_hint(Action::Help),
+                Span::raw(" for help or "),
+                self.key_hint(Action::Quit),
                 Span::raw(" to quit."),
             ]),
             Spans::from(vec![
                 Span::raw("Note: Press "),
-                Span::styled("tab", Style::default().add_modifier(Modifier::BOLD)),
+                self.key_hint(Action::Restart),
                 Span::raw(" during a test to quick restart."),
             ]),
+        ]);
+
+        if let Some(status) = status {
+            summary.push(Spans::from(Span::styled(
+                status.to_string(),
+                Style::default().fg(self.theme.correct),
+            )));
+        }
+
+        // Long lines wrap, so they take more than one row
+        let summary_width = self.terminal.size()?.width.saturating_sub(2).max(1) as usize;
+        let summary_height = summary
+            .iter()
+            .map(|line| line.width().max(1).div_ceil(summary_width) as u16)
+            .sum::<u16>() + 2;
+        let summary = Paragraph::new(Text::from(summary))
+            .block(self.results_block(title))
+            .wrap(Wrap { trim: true });
+
+        let report = match &self.report {
+            Some(report) => report,
+            None => return Ok(()),
+        };
+
+        // Without a text to match there is no accuracy, only what was typed
+        let (speed, characters) = if self.settings.zen {
+            (vec![
+                Self::stat("Gross WPM", format!("{:.1}", report.gross_wpm)),
+                Self::stat("Net WPM", format!("{:.1}", report.net_wpm)),
+            ], vec![
+                Self::stat("Typed", report.correct.to_string()),
+                Self::stat("Words", self.words.len().to_string()),
+            ])
+        } else {
+            (vec![
+                Self::stat("Gross WPM", format!("{:.1}", report.gross_wpm)),
+                Self::stat("Net WPM", format!("{:.1}", report.net_wpm)),
+                Self::stat("Accuracy", format!("{:.1}%", report.accuracy)),
+            ], vec![
+                Self::stat("Correct", report.correct.to_string()),
+                Self::stat("Incorrect", report.incorrect.to_string()),
+                Self::stat("Extra", report.extra.to_string()),
+                Self::stat("Missed", report.missed.to_string()),
+            ])
+        };
+        let speed = Paragraph::new(Text::from(speed)).block(self.results_block("Speed"));
+        let characters = Paragraph::new(Text::from(characters)).block(self.results_block("Characters"));
+
+        let test = Paragraph::new(Text::from(vec![
+            Self::stat("Consistency", format!("{:.1}%", report.consistency)),
+            Self::stat("Peak burst", format!("{:.1} WPM", report.peak_burst)),
+            Self::stat("Duration", format!("{:.2}s", report.duration)),
+            Self::stat("Mode", report.mode.clone()),
+            Self::stat("Seed", report.seed.map_or("none".to_string(), |seed| seed.to_string())),
         ]))
-        .block(results_block)
-        .wrap(Wrap { trim: true });
+        .block(self.results_block("Test"));
 
         let net_wpm_dataset: Vec<(f64, f64)> = iter::zip(self.log.time.clone(), self.log.net_wpm.clone()).collect();
         let gross_wpm_dataset: Vec<(f64, f64)> = iter::zip(self.log.time.clone(), self.log.gross_wpm.clone()).collect();
+        let raw_wpm_dataset = self.log.raw_wpm_per_second(self.elapsed_seconds);
+        let errors = self.log.errors_per_second(self.elapsed_seconds);
+
+        let max_wpm = self.log.gross_wpm.iter()
+            .chain(raw_wpm_dataset.iter().map(|(_, wpm)| wpm))
+            .cloned()
+            .fold(0.0, f64::max) + 10.0;
+
+        // Errors share the canvas with WPM, so they are scaled onto the WPM
+        // bounds and read off a second axis drawn beside the chart
+        let max_errors = errors.iter().map(|(_, count)| *count).fold(1.0, f64::max);
+        let errors_dataset: Vec<(f64, f64)> = errors
+            .iter()
+            .map(|(time, count)| (*time, count / max_errors * max_wpm))
+            .collect();
 
         let datasets = vec![
             Dataset::default()
@@ -480,11 +1531,23 @@ impl TypingTest<'_> {
                 .graph_type(GraphType::Line)
                 .style(Style::default().fg(Color::Magenta))
                 .data(&gross_wpm_dataset),
+            Dataset::default()
+                .name("raw")
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(self.theme.fg))
+                .data(&raw_wpm_dataset),
+            Dataset::default()
+                .name("errors")
+                .marker(symbols::Marker::Dot)
+                .graph_type(GraphType::Scatter)
+                .style(Style::default().fg(self.theme.incorrect))
+                .data(&errors_dataset),
         ];
 
-        let time_labels = ["0", &format!("{:.0}", TEST_DURATION / 2.0), &format!("{:.0}", TEST_DURATION)];
-        let max_gross_wpm = *self.log.gross_wpm.iter().max_by(|a, b| a.partial_cmp(b).unwrap()).unwrap() + 10.0;
-        let wpm_labels = ["0", &format!("{:.0}", max_gross_wpm / 2.0), &format!("{:.0}", max_gross_wpm)];
+        let duration = self.chart_duration();
+        let time_labels = ["0", &format!("{:.0}", duration / 2.0), &format!("{:.0}", duration)];
+        let wpm_labels = ["0", &format!("{:.0}", max_wpm / 2.0), &format!("{:.0}", max_wpm)];
 
         let chart = Chart::new(datasets)
             .block(Block::default()
@@ -494,115 +1557,502 @@ impl TypingTest<'_> {
             .x_axis(Axis::default()
                 .title(Span::styled("Time", Style::default().fg(self.theme.fg)))
                 .style(Style::default().fg(self.theme.highlight))
-                .bounds([0.0, TEST_DURATION])
+                .bounds([0.0, duration])
                 .labels(time_labels.iter().cloned().map(Span::from).collect()))
             .y_axis(Axis::default()
                 .title(Span::styled("Words per Minute", Style::default().fg(self.theme.fg)))
                 .style(Style::default().fg(self.theme.highlight))
-                .bounds([0.0, max_gross_wpm])
+                .bounds([0.0, max_wpm])
                 .labels(wpm_labels.iter().cloned().map(Span::from).collect()));
 
+        let errors_style = Style::default().fg(self.theme.incorrect);
+        let slowest = Paragraph::new(Text::from(self.ngram_lines(&self.ngram_timer.ngrams, 1)))
+            .block(self.results_block("Slowest"));
+
+        let popup = overlay.map(|overlay| {
+            let (title, lines) = self.overlay_text(overlay);
+            let width = lines.iter().map(|line| line.width()).max().unwrap_or(0) as u16 + 4;
+            let height = lines.len() as u16 + 2;
+            (Paragraph::new(Text::from(lines)).block(self.results_block(title)), width, height)
+        });
 
         self.terminal.draw(|frame| {
             let size = frame.size();
+            // Stats sit side by side when there is room and stack otherwise
+            let wide = size.width >= RESULTS_COLUMN_WIDTH * 3;
+            let stats_height = if wide { 7 } else { 18 };
+
             let layout = Layout::default()
                 .direction(Direction::Vertical)
                 .constraints(
                     [
-                        Constraint::Ratio(1, 4),
-                        Constraint::Ratio(3, 4),
-                        Constraint::Min(15),
+                        Constraint::Length(summary_height),
+                        Constraint::Length(stats_height),
+                        Constraint::Min(8),
                     ]
                     .as_ref(),
                 )
                 .split(size);
 
+            let stats_layout = if wide {
+                Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints(
+                        [
+                            Constraint::Ratio(1, 3),
+                            Constraint::Ratio(1, 3),
+                            Constraint::Ratio(1, 3),
+                        ]
+                        .as_ref(),
+                    )
+                    .split(layout[1])
+            } else {
+                Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints(
+                        [
+                            Constraint::Length(5),
+                            Constraint::Length(6),
+                            Constraint::Length(7),
+                        ]
+                        .as_ref(),
+                    )
+                    .split(layout[1])
+            };
+
+            frame.render_widget(summary, layout[0]);
+            frame.render_widget(speed, stats_layout[0]);
+            frame.render_widget(characters, stats_layout[1]);
+            frame.render_widget(test, stats_layout[2]);
             let chart_layout = Layout::default()
                 .direction(Direction::Horizontal)
                 .constraints(
                     [
-                        Constraint::Ratio(1, 1),
+                        Constraint::Min(0),
+                        Constraint::Length(ERRORS_AXIS_WIDTH),
+                        Constraint::Length(if wide { NGRAMS_WIDTH } else { 0 }),
                     ]
                     .as_ref(),
                 )
-                .split(layout[1]);
+                .split(layout[2]);
+
+            // Lines up with the chart's y axis, which ends above the x labels
+            let errors_axis_area = Rect {
+                y: chart_layout[1].y + 1,
+                height: chart_layout[1].height.saturating_sub(3),
+                ..chart_layout[1]
+            };
+            let mut errors_axis = vec![Spans::default(); errors_axis_area.height as usize];
+            if errors_axis.len() >= 3 {
+                let middle = errors_axis.len() / 2;
+                let last = errors_axis.len() - 1;
+                errors_axis[0] = Spans::from(format!(" {:.0} err", max_errors));
+                errors_axis[middle] = Spans::from(format!(" {:.1}", max_errors / 2.0));
+                errors_axis[last] = Spans::from(" 0");
+            }
+            let errors_axis = Paragraph::new(Text::from(errors_axis))
+                .style(errors_style);
 
-            frame.render_widget(results, layout[0]);
             frame.render_widget(chart, chart_layout[0]);
+            frame.render_widget(errors_axis, errors_axis_area);
+            frame.render_widget(slowest, chart_layout[2]);
+
+            if let Some((popup, width, height)) = popup {
+                let area = Self::centered(width, height, size);
+                frame.render_widget(Clear, area);
+                frame.render_widget(popup, area);
+            }
         })?;
 
+        Ok(())
+    }
+
+    fn export_results(&self) -> String {
+        let report = match &self.report {
+            Some(report) => report,
+            None => return "Nothing to export".to_string(),
+        };
+
+        match env::current_dir().and_then(|dir| export::write_all(report, &dir)) {
+            Ok(stem) => format!("Exported to {}.{{csv,json,md}}", stem.display()),
+            Err(error) => format!("Export failed: {}", error),
+        }
+    }
+
+    fn show_results(&mut self) -> Result<(), io::Error> {
         let mut restart = false;
+        let mut status = None;
+        let mut overlay = None;
+
+        self.draw_results(None, None)?;
 
         loop {
-            if poll(Duration::from_millis(((1.0 / TIMER_REFRESH_RATE) * 1000.0).round() as u64)).unwrap() {
-                if let Event::Key(event) = read().unwrap() {
-                    match event.code {
-                        KeyCode::Esc => {
-                            self.cleanup_terminal();
+            let event = match read()? {
+                Event::Key(event) => event,
+                Event::Resize(_, _) => {
+                    self.draw_results(status.as_deref(), overlay)?;
+                    continue;
+                }
+                _ => continue,
+            };
+
+            match overlay {
+                Some(Overlay::Menu(selected)) => match event.code {
+                    KeyCode::Up => overlay = Some(Overlay::Menu(selected.saturating_sub(1))),
+                    KeyCode::Down => {
+                        overlay = Some(Overlay::Menu((selected + 1).min(DURATIONS.len() - 1)))
+                    }
+                    KeyCode::Enter => {
+                        self.settings.duration = DURATIONS[selected];
+                        self.cleanup_terminal()?;
+                        self.reset();
+                        restart = true;
+                        break;
+                    }
+                    KeyCode::Esc => overlay = None,
+                    _ => (),
+                },
+                Some(Overlay::Help) | Some(Overlay::Review) | Some(Overlay::Paused) => overlay = None,
+                None => match self.settings.keymap.action(KeyBinding::from_event(event)) {
+                    Some(Action::Quit) => {
+                        self.cleanup_terminal()?;
+                        break;
+                    }
+                    Some(Action::Restart) | Some(Action::NextTest) => {
+                        self.cleanup_terminal()?;
+                        self.reset();
+                        restart = true;
+                        break;
+                    }
+                    Some(Action::RepeatTest) => {
+                        self.cleanup_terminal()?;
+                        self.repeat();
+                        restart = true;
+                        break;
+                    }
+                    Some(Action::PracticeMissed) => {
+                        if self.practice_missed() {
+                            self.cleanup_terminal()?;
+                            restart = true;
                             break;
                         }
-                        KeyCode::Char(c) => match c {
-                            'q' => {
-                                self.cleanup_terminal();
-                                break;
-                            }
-                            'r' => {
-                                self.cleanup_terminal();
-                                self.reset();
-                                restart = true;
-                                break;
-                            }
-                            _ => (),
-                        },
-                        _ => (),
+                        status = Some("There are no missed words to practice".to_string());
                     }
-                }
+                    Some(Action::Export) => status = Some(self.export_results()),
+                    Some(Action::ToggleStats) => self.show_stats()?,
+                    Some(Action::OpenMenu) => {
+                        let current = DURATIONS
+                            .iter()
+                            .position(|duration| *duration == self.settings.duration)
+                            .unwrap_or(0);
+                        overlay = Some(Overlay::Menu(current));
+                    }
+                    Some(Action::Help) => overlay = Some(Overlay::Help),
+                    Some(Action::Review) => overlay = Some(Overlay::Review),
+                    Some(Action::Finish) | Some(Action::Pause) | Some(Action::DeleteWord) | Some(Action::DeleteLine) | None => continue,
+                },
             }
+
+            self.draw_results(status.as_deref(), overlay)?;
         }
 
         if restart {
-            self.start_test();
+            self.start_test()?;
         }
 
         Ok(())
     }
 
-    pub fn start_test(&mut self) {
-        let (_, mut rx): (_, mpsc::Receiver<()>) = mpsc::channel();
-        self.refresh();
+    fn draw_stats(&mut self, reports: &[report::Report], filter: &stats::Filter,
+                  ngrams: &ngrams::Ngrams) -> Result<(), io::Error> {
+        let reports: Vec<&report::Report> = reports.iter().filter(|report| filter.matches(report)).collect();
 
-        let mut restart = false;
+        let net_wpm: Vec<f64> = reports.iter().map(|report| report.net_wpm).collect();
+        let accuracy: Vec<f64> = reports.iter().map(|report| report.accuracy).collect();
+        let best_net_wpm = net_wpm.iter().cloned().fold(0.0, f64::max);
+        let average = |values: &[f64]| {
+            stats::rolling_average(values, 10).last().cloned().unwrap_or(0.0)
+        };
+
+        let summary = Paragraph::new(Text::from(vec![
+            Self::stat("Showing", filter.describe()),
+            Self::stat("Tests completed", reports.len().to_string()),
+            Self::stat("Time typed", stats::format_duration(stats::total_seconds(&reports))),
+            Self::stat("Best net WPM", format!("{:.1}", best_net_wpm)),
+            Self::stat("Last 10 average",
+                             format!("{:.1} WPM, {:.1}% accuracy", average(&net_wpm), average(&accuracy))),
+            Spans::from(vec![
+                Span::raw("Press "),
+                Span::styled("m", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(" to filter by mode, "),
+                Span::styled("w", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(" to filter by word list or "),
+                self.key_hint(Action::Quit),
+                Span::raw(" to go back."),
+            ]),
+        ]))
+        .block(self.results_block("Statistics"))
+        .wrap(Wrap { trim: true });
+
+        let indexed = |values: &[f64]| -> Vec<(f64, f64)> {
+            values.iter().enumerate().map(|(i, value)| (i as f64 + 1.0, *value)).collect()
+        };
+        let net_wpm_dataset = indexed(&net_wpm);
+        let average_10_dataset = indexed(&stats::rolling_average(&net_wpm, 10));
+        let average_100_dataset = indexed(&stats::rolling_average(&net_wpm, 100));
+        let accuracy_dataset = indexed(&accuracy);
+        let accuracy_average_dataset = indexed(&stats::rolling_average(&accuracy, 10));
+
+        let tests = (reports.len() as f64).max(2.0);
+        let test_labels = ["1", &format!("{:.0}", tests)];
+        let max_wpm = best_net_wpm + 10.0;
+        let wpm_labels = ["0", &format!("{:.0}", max_wpm / 2.0), &format!("{:.0}", max_wpm)];
+
+        let wpm_chart = Chart::new(vec![
+            Dataset::default()
+                .name("net")
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(Color::Cyan))
+                .data(&net_wpm_dataset),
+            Dataset::default()
+                .name("avg 10")
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(Color::Magenta))
+                .data(&average_10_dataset),
+            Dataset::default()
+                .name("avg 100")
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(self.theme.highlight))
+                .data(&average_100_dataset),
+        ])
+            .block(self.results_block("WPM"))
+            .x_axis(Axis::default()
+                .title(Span::styled("Test", Style::default().fg(self.theme.fg)))
+                .style(Style::default().fg(self.theme.highlight))
+                .bounds([1.0, tests])
+                .labels(test_labels.iter().cloned().map(Span::from).collect()))
+            .y_axis(Axis::default()
+                .title(Span::styled("Words per Minute", Style::default().fg(self.theme.fg)))
+                .style(Style::default().fg(self.theme.highlight))
+                .bounds([0.0, max_wpm])
+                .labels(wpm_labels.iter().cloned().map(Span::from).collect()));
+
+        let accuracy_chart = Chart::new(vec![
+            Dataset::default()
+                .name("accuracy")
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(self.theme.correct))
+                .data(&accuracy_dataset),
+            Dataset::default()
+                .name("avg 10")
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(Color::Magenta))
+                .data(&accuracy_average_dataset),
+        ])
+            .block(self.results_block("Accuracy"))
+            .x_axis(Axis::default()
+                .style(Style::default().fg(self.theme.highlight))
+                .bounds([1.0, tests])
+                .labels(test_labels.iter().cloned().map(Span::from).collect()))
+            .y_axis(Axis::default()
+                .style(Style::default().fg(self.theme.highlight))
+                .bounds([0.0, 100.0])
+                .labels(["0", "50", "100"].iter().cloned().map(Span::from).collect()));
+
+        let activity = stats::daily_activity(&reports, report::Report::now());
+        let busiest_day = activity.iter().flatten().cloned().max().unwrap_or(0).max(1);
+        let activity_block = self.results_block("Daily Activity");
+        // N-grams are kept across every test, so the filter does not apply to them
+        let slowest = Paragraph::new(Text::from(self.ngram_lines(ngrams, NGRAM_HISTORY_MIN_COUNT)))
+            .block(self.results_block("Slowest N-grams"));
+        let (empty_style, active_style) = (Style::default().fg(self.theme.fg),
+                                           Style::default().fg(self.theme.correct));
+
+        self.terminal.draw(|frame| {
+            let size = frame.size();
+            let layout = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(
+                    [
+                        Constraint::Length(8),
+                        Constraint::Min(8),
+                        Constraint::Length(SLOWEST_NGRAMS as u16 + 2),
+                    ]
+                    .as_ref(),
+                )
+                .split(size);
+
+            let lower_layout = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(
+                    [
+                        Constraint::Ratio(1, 2),
+                        Constraint::Length(NGRAMS_WIDTH),
+                        Constraint::Ratio(1, 2),
+                    ]
+                    .as_ref(),
+                )
+                .split(layout[2]);
+
+            // Two columns per week after the weekday labels, newest week on the right
+            let visible_weeks = ((lower_layout[2].width.saturating_sub(6) / 2) as usize)
+                .min(activity.len());
+            let weeks = &activity[activity.len() - visible_weeks..];
+            let heatmap: Vec<Spans> = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"]
+                .iter()
+                .enumerate()
+                .map(|(weekday, label)| {
+                    let mut row = vec![Span::styled(format!("{} ", label), empty_style)];
+                    row.extend(weeks.iter().map(|week| match week[weekday] {
+                        0 => Span::styled("· ", empty_style),
+                        tests => {
+                            let shades = ['░', '▒', '▓', '█'];
+                            let level = ((tests * shades.len() as u32 - 1) / busiest_day) as usize;
+                            Span::styled(format!("{} ", shades[level]), active_style)
+                        }
+                    }));
+                    Spans::from(row)
+                })
+                .collect();
+
+            frame.render_widget(summary, layout[0]);
+            frame.render_widget(wpm_chart, layout[1]);
+            frame.render_widget(accuracy_chart, lower_layout[0]);
+            frame.render_widget(slowest, lower_layout[1]);
+            frame.render_widget(Paragraph::new(Text::from(heatmap)).block(activity_block),
+                                lower_layout[2]);
+        })?;
+
+        Ok(())
+    }
+
+    fn show_stats(&mut self) -> Result<(), io::Error> {
+        let reports = history::load()?;
+        let ngrams = ngrams::Ngrams::load().unwrap_or_else(|_| ngrams::Ngrams::new());
+        let modes = stats::distinct(&reports, |report| &report.mode);
+        let word_lists = stats::distinct(&reports, |report| &report.word_list);
+        let mut filter = stats::Filter::new();
 
         loop {
-            if rx.try_recv().is_ok() {
-                self.elapsed_seconds += 1.0 / TIMER_REFRESH_RATE;
-                if self.elapsed_seconds < TEST_DURATION {
-                    self.refresh();
-                } else {
-                    break;
+            self.draw_stats(&reports, &filter, &ngrams)?;
+
+            if let Event::Key(event) = read()? {
+                match self.settings.keymap.action(KeyBinding::from_event(event)) {
+                    Some(Action::Quit) | Some(Action::ToggleStats) => break,
+                    _ => match event.code {
+                        KeyCode::Char('m') => filter.mode = stats::cycle(&filter.mode, &modes),
+                        KeyCode::Char('w') => {
+                            filter.word_list = stats::cycle(&filter.word_list, &word_lists)
+                        }
+                        _ => (),
+                    },
                 }
             }
+        }
+
+        Ok(())
+    }
+
+    pub fn start_stats(&mut self) -> Result<(), io::Error> {
+        self.show_stats()?;
+        self.cleanup_terminal()
+    }
+
+    pub fn start_test(&mut self) -> Result<(), io::Error> {
+        self.refresh()?;
+
+        let mut restart = false;
+        let mut quit = false;
+        let mut last_redraw = Instant::now();
+
+        loop {
+            self.update_elapsed();
+            self.sample();
+
+            if self.out_of_time() {
+                break;
+            }
 
-            if poll(Duration::from_millis(((1.0 / TIMER_REFRESH_RATE) * 1000.0).round() as u64)).unwrap() {
-                if let Event::Key(event) = read().unwrap() {
-                    if self.elapsed_seconds == 0.0 {
-                        rx = TypingTest::start_timer();
+            if self.start_time.is_some() && last_redraw.elapsed() >= REDRAW_INTERVAL {
+                self.refresh()?;
+                last_redraw = Instant::now();
+            }
+
+            if let Some(failure) = self.check_fail_conditions() {
+                self.failure = Some(failure);
+                break;
+            }
+
+            if poll(self.poll_timeout(last_redraw))? {
+                let event = read()?;
+                if let Event::Resize(_, _) = event {
+                    self.refresh()?;
+                }
+
+                if let Event::Key(event) = event {
+                    let key = KeyBinding::from_event(event);
+                    let action = if key.is_text() {
+                        None
+                    } else {
+                        self.settings.keymap.action(key)
+                    };
+
+                    // Keystrokes are timed when read, not when the next redraw happens
+                    match self.start_time {
+                        Some(_) => self.update_elapsed(),
+                        None if Self::starts_clock(action, key, event.code) => {
+                            self.start_time = Some(Instant::now())
+                        }
+                        None => (),
                     }
-                    match event.code {
-                        KeyCode::Esc => {
-                            self.cleanup_terminal();
+                    if self.out_of_time() {
+                        break;
+                    }
+                    self.sample();
+
+                    match (action, event.code) {
+                        (Some(action), _) if self.quits(action) => {
+                            self.cleanup_terminal()?;
+                            quit = true;
                             break;
                         }
-                        KeyCode::Tab => {
-                            self.cleanup_terminal();
+                        (Some(Action::Finish), _) => break,
+                        (Some(Action::Pause), _) => self.pause()?,
+                        (Some(Action::Restart), _) | (Some(Action::NextTest), _) => {
+                            self.cleanup_terminal()?;
                             self.reset();
                             restart = true;
                             break;
                         }
-                        KeyCode::Char(c) => {
+                        (Some(Action::RepeatTest), _) => {
+                            self.cleanup_terminal()?;
+                            self.repeat();
+                            restart = true;
+                            break;
+                        }
+                        (Some(Action::DeleteWord), _) => {
+                            self.track_activity();
+                            self.delete_word();
+                        }
+                        (Some(Action::DeleteLine), _) => {
+                            self.track_activity();
+                            self.delete_line();
+                        }
+                        (Some(_), _) => (),
+                        (None, KeyCode::Char(c)) if key.is_text() => {
+                            self.track_activity();
                             self.update_char(c);
+                            if self.finished_text() {
+                                break;
+                            }
                         }
-                        KeyCode::Backspace => {
+                        (None, KeyCode::Backspace) => {
+                            self.track_activity();
                             self.backspace();
                         }
                         _ => (),
@@ -612,9 +2062,294 @@ impl TypingTest<'_> {
         }
 
         if restart {
-            self.start_test();
+            self.start_test()
         } else {
-            self.show_results();
+            self.validate(quit);
+            self.show_results()
+        }
+    }
+}
+
+impl<B: Backend> Drop for TypingTest<'_, B> {
+    fn drop(&mut self) {
+        // Nothing to restore when the terminal was never set up
+        if terminal::is_raw_mode_enabled().unwrap_or(false) {
+            let _ = TypingTest::restore_terminal();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use tui::backend::TestBackend;
+
+    fn typing_test(words: &[&str], width: u16) -> TypingTest<'static, TestBackend> {
+        let terminal = Terminal::new(TestBackend::new(width, 24)).unwrap();
+        let mut test = TypingTest::with_terminal(Settings::new(), terminal);
+        test.words = words.iter().map(|word| word.to_string()).collect();
+        test.build_text();
+        // Sets the text width from the terminal
+        test.refresh().unwrap();
+        test
+    }
+
+    fn type_text<B: Backend>(test: &mut TypingTest<'_, B>, text: &str) {
+        for c in text.chars() {
+            test.update_char(c);
+        }
+    }
+
+    fn target<B: Backend>(test: &TypingTest<'_, B>) -> String {
+        test.text.iter().map(|span| span.content.as_ref()).collect()
+    }
+
+    fn typed<B: Backend>(test: &TypingTest<'_, B>) -> String {
+        test.typed.iter().collect()
+    }
+
+    #[test]
+    fn typing_keeps_the_target_text() {
+        let mut test = typing_test(&["hello", "world"], 80);
+        type_text(&mut test, "hellx wo");
+        assert_eq!(target(&test), "hello world ");
+        assert_eq!(test.position, 8);
+        assert_eq!(typed(&test), "hellx wo");
+        assert_eq!(test.mistakes.iter().cloned().collect::<Vec<usize>>(), vec![4]);
+        assert_eq!(test.missed_words(), vec!["hello".to_string()]);
+    }
+
+    #[test]
+    fn typing_past_the_end_does_not_panic() {
+        let mut test = typing_test(&["ab"], 80);
+        type_text(&mut test, "ab cd");
+        assert_eq!(test.position, 3);
+        assert_eq!(typed(&test), "ab ");
+        assert_eq!(target(&test), "ab ");
+
+        test.backspace();
+        assert_eq!(test.position, 2);
+        type_text(&mut test, " ");
+        assert_eq!(test.position, 3);
+    }
+
+    #[test]
+    fn a_final_keystroke_does_not_skew_burst_or_consistency() {
+        let mut log = Log::new();
+        // Five keystrokes a second for 12 seconds, then one that ends the test
+        for i in 0..60 {
+            log.keystroke((i as f64 + 0.5) / 5.0, true);
+        }
+        log.keystroke(12.02, true);
+
+        assert!((log.peak_burst(12.02) - 60.0).abs() < 1e-9);
+        assert!((log.consistency(12.02) - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn a_final_keystroke_does_not_spike_the_chart() {
+        let mut log = Log::new();
+        for i in 0..60 {
+            log.keystroke((i as f64 + 0.5) / 5.0, true);
+        }
+        log.keystroke(12.02, true);
+
+        let raw = log.raw_wpm_per_second(12.02);
+        assert_eq!(raw.len(), 12);
+        assert!(raw.iter().all(|(_, wpm)| (wpm - 60.0).abs() < 1e-9));
+    }
+
+    #[test]
+    fn paused_time_is_left_out() {
+        let mut test = typing_test(&["hello"], 80);
+        // Pausing before the first keystroke leaves the clock stopped
+        test.resume(Duration::from_secs(5));
+        assert!(test.start_time.is_none());
+
+        test.start_time = Some(Instant::now() - Duration::from_secs(10));
+        test.paused = true;
+        test.resume(Duration::from_secs(8));
+        test.update_elapsed();
+        assert!(!test.paused);
+        assert!((test.elapsed_seconds - 2.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn only_typing_starts_the_clock() {
+        let starts = |action, text, code| {
+            TypingTest::<TestBackend>::starts_clock(action, KeyBinding::parse(text).unwrap(), code)
+        };
+        assert!(starts(None, "a", KeyCode::Char('a')));
+        assert!(starts(None, "backspace", KeyCode::Backspace));
+        assert!(starts(Some(Action::DeleteWord), "ctrl+w", KeyCode::Char('w')));
+        assert!(!starts(Some(Action::Pause), "ctrl+p", KeyCode::Char('p')));
+        assert!(!starts(Some(Action::Help), "f1", KeyCode::F(1)));
+    }
+
+    #[test]
+    fn finishing_early_only_counts_in_zen_mode() {
+        let mut test = typing_test(&["hello", "world"], 80);
+        test.lesson = Some(lesson::Lesson::new(1));
+        type_text(&mut test, "hello worl");
+        test.elapsed_seconds = 2.0;
+
+        test.judge(test.quits(Action::Finish));
+        assert!(matches!(test.invalid, Some(Invalid::Quit)));
+        assert_eq!(test.lesson.as_ref().unwrap().passed, Some(false));
+
+        test.settings.zen = true;
+        assert!(!test.quits(Action::Finish));
+        assert!(test.quits(Action::Quit));
+    }
+
+    #[test]
+    fn zen_mode_types_freely() {
+        let mut settings = Settings::new();
+        settings.zen = true;
+        let terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+        let mut test = TypingTest::with_terminal(settings, terminal);
+        assert_eq!(target(&test), " ");
+
+        type_text(&mut test, "free  typing!");
+        test.backspace();
+        assert_eq!(target(&test), "free  typing ");
+        assert_eq!(test.position, 12);
+        assert_eq!(test.typed_words(), ["free", "typing"]);
+        assert_eq!(test.score.total_incorrect_characters(), 0.0);
+        assert!(!test.finished_text());
+        assert_eq!(test.time_limit(), None);
+    }
+
+    #[test]
+    fn zen_mode_wraps_the_typed_text() {
+        let mut settings = Settings::new();
+        settings.zen = true;
+        let terminal = Terminal::new(TestBackend::new(20, 24)).unwrap();
+        let mut test = TypingTest::with_terminal(settings, terminal);
+        test.refresh().unwrap();
+
+        type_text(&mut test, "aaaa bbbb cccc dddd eeee");
+        assert_eq!(test.line_starts(), vec![0, 15]);
+        let (lines, cursor) = test.viewport();
+        assert_eq!(cursor, (1, 9));
+        assert!(lines.iter().all(|line| line.width() <= test.text_width));
+    }
+
+    #[test]
+    fn the_text_is_finished_after_its_last_word() {
+        let mut test = typing_test(&["ab", "cd"], 80);
+        type_text(&mut test, "ab c");
+        assert!(!test.finished_text());
+        type_text(&mut test, "x");
+        assert!(test.finished_text());
+    }
+
+    #[test]
+    fn backspace_at_the_start_does_nothing() {
+        let mut test = typing_test(&["ab"], 80);
+        test.backspace();
+        assert_eq!(test.position, 0);
+        assert_eq!(test.score.total_characters(), 0.0);
+    }
+
+    #[test]
+    fn backspace_undoes_scoring() {
+        let mut test = typing_test(&["abc"], 80);
+        type_text(&mut test, "ax");
+        test.backspace();
+        test.backspace();
+        assert_eq!(test.position, 0);
+        assert_eq!(test.score.total_characters(), 0.0);
+        // The mistake still counts against accuracy
+        assert_eq!(test.score.total_incorrect_characters(), 1.0);
+    }
+
+    #[test]
+    fn delete_word_goes_back_to_the_word_start() {
+        let mut test = typing_test(&["one", "two", "three"], 80);
+        type_text(&mut test, "one tw");
+        test.delete_word();
+        assert_eq!(test.position, 4);
+        assert_eq!(typed(&test), "one ");
+
+        // At the start of a word the previous word is deleted
+        test.delete_word();
+        assert_eq!(test.position, 0);
+        assert_eq!(target(&test), "one two three ");
+    }
+
+    #[test]
+    fn lines_wrap_by_word() {
+        // A width of 14 leaves 12 columns inside the borders
+        let test = typing_test(&["alpha", "beta", "gamma", "delta", "epsilon"], 14);
+        assert_eq!(test.line_starts(), vec![0, 11, 23]);
+
+        // The space after "bbbbb" would end at column 11 of 10
+        let mut test = typing_test(&["aaaa", "bbbbb", "cc"], 12);
+        assert_eq!(test.line_starts(), vec![0, 5]);
+        type_text(&mut test, "aaaa bbbbb");
+        assert_eq!(test.viewport().1, (1, 5));
+    }
+
+    #[test]
+    fn delete_line_goes_back_to_the_line_start() {
+        let mut test = typing_test(&["alpha", "beta", "gamma", "delta"], 14);
+        type_text(&mut test, "alpha beta gam");
+        test.delete_line();
+        assert_eq!(test.position, 11);
+        test.delete_line();
+        assert_eq!(test.position, 0);
+    }
+
+    #[test]
+    fn viewport_follows_the_cursor() {
+        let words = ["aaaa", "bbbb", "cccc", "dddd", "eeee", "ffff", "gggg"];
+        let mut test = typing_test(&words, 12);
+        let first_line = |test: &TypingTest<'_, TestBackend>| {
+            let (lines, cursor) = test.viewport();
+            let text: String = lines[0].0.iter().map(|span| span.content.as_ref()).collect();
+            (text, cursor)
+        };
+
+        assert_eq!(first_line(&test), ("aaaa bbbb ".to_string(), (0, 0)));
+        type_text(&mut test, "aaaa bbbb cc");
+        assert_eq!(first_line(&test), ("aaaa bbbb ".to_string(), (1, 2)));
+        type_text(&mut test, "cc dddd eeee f");
+        assert_eq!(first_line(&test), ("cccc dddd ".to_string(), (1, 6)));
+    }
+
+    #[test]
+    fn repeat_starts_over_on_the_same_words() {
+        let mut test = typing_test(&["same", "words"], 80);
+        type_text(&mut test, "sane");
+        test.repeat();
+        assert_eq!(test.position, 0);
+        assert!(test.typed.is_empty());
+        assert!(test.mistakes.is_empty());
+        assert_eq!(target(&test), "same words ");
+    }
+
+    proptest! {
+        #[test]
+        fn editing_never_loses_the_target(keys in prop::collection::vec(
+            prop_oneof![
+                "[a-e ]".prop_map(|key| key.chars().next()),
+                Just(None),
+            ],
+            0..60,
+        )) {
+            let mut test = typing_test(&["abc", "de", "a"], 80);
+            for key in keys {
+                match key {
+                    Some(c) => test.update_char(c),
+                    None => test.backspace(),
+                }
+                prop_assert_eq!(test.position, test.typed.len());
+                prop_assert!(test.position <= 9);
+            }
+            prop_assert_eq!(target(&test), "abc de a ");
+            prop_assert!(test.score.total_characters() >= 0.0);
         }
     }
 }
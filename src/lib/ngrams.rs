@@ -0,0 +1,158 @@
+use super::report::data_dir;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+
+const NGRAMS_FILE: &str = "ngrams.json";
+// Longer gaps are pauses rather than the time it takes to reach a key
+const MAX_TRANSITION: f64 = 2.0;
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Timing {
+    pub count: u32,
+    pub total_seconds: f64,
+}
+
+impl Timing {
+    pub fn average(&self) -> f64 {
+        self.total_seconds / self.count.max(1) as f64
+    }
+}
+
+// Time between keystrokes within each character pair and triple. Triples are
+// stored per keystroke as well, so they rank alongside pairs.
+#[derive(Default, Serialize, Deserialize)]
+pub struct Ngrams {
+    timings: BTreeMap<String, Timing>,
+}
+
+impl Ngrams {
+    pub fn new() -> Ngrams {
+        Ngrams::default()
+    }
+
+    pub fn record(&mut self, ngram: &str, seconds: f64) {
+        let timing = self.timings.entry(ngram.to_string()).or_default();
+        timing.count += 1;
+        timing.total_seconds += seconds;
+    }
+
+    pub fn merge(&mut self, other: &Ngrams) {
+        for (ngram, timing) in &other.timings {
+            let merged = self.timings.entry(ngram.clone()).or_default();
+            merged.count += timing.count;
+            merged.total_seconds += timing.total_seconds;
+        }
+    }
+
+    // The n-grams with the highest average time, ignoring ones seen fewer
+    // than `min_count` times
+    pub fn slowest(&self, limit: usize, min_count: u32) -> Vec<(&str, Timing)> {
+        let mut slowest: Vec<(&str, Timing)> = self
+            .timings
+            .iter()
+            .filter(|(_, timing)| timing.count >= min_count)
+            .map(|(ngram, timing)| (ngram.as_str(), *timing))
+            .collect();
+        slowest.sort_by(|a, b| b.1.average().total_cmp(&a.1.average()));
+        slowest.truncate(limit);
+        slowest
+    }
+
+    pub fn load() -> Result<Ngrams, io::Error> {
+        match fs::read_to_string(data_dir()?.join(NGRAMS_FILE)) {
+            Ok(contents) => Ok(serde_json::from_str(&contents)?),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Ngrams::new()),
+            Err(error) => Err(error),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), io::Error> {
+        fs::write(data_dir()?.join(NGRAMS_FILE), serde_json::to_string(self)?)
+    }
+}
+
+// Follows the correct keystrokes within the current word. A mistake, a
+// deletion or a space starts over, so only clean runs of letters are timed.
+pub struct Timer {
+    recent: Vec<(char, f64)>,
+    pub ngrams: Ngrams,
+}
+
+impl Timer {
+    pub fn new() -> Timer {
+        Timer {
+            recent: Vec::new(),
+            ngrams: Ngrams::new(),
+        }
+    }
+
+    pub fn keystroke(&mut self, character: char, time: f64, correct: bool) {
+        if !correct || character == ' ' {
+            self.interrupt();
+            return;
+        }
+
+        if self.recent.last().is_some_and(|(_, last)| time - last > MAX_TRANSITION) {
+            self.interrupt();
+        }
+        if self.recent.len() == 3 {
+            self.recent.remove(0);
+        }
+        self.recent.push((character, time));
+
+        for length in 2..=self.recent.len() {
+            let start = self.recent.len() - length;
+            let ngram: String = self.recent[start..].iter().map(|(c, _)| c).collect();
+            self.ngrams.record(&ngram, (time - self.recent[start].1) / (length - 1) as f64);
+        }
+    }
+
+    pub fn interrupt(&mut self) {
+        self.recent.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn times_pairs_and_triples_within_words() {
+        let mut timer = Timer::new();
+        for (character, time) in [('t', 0.0), ('h', 0.1), ('e', 0.4), (' ', 0.5), ('e', 0.6)] {
+            timer.keystroke(character, time, true);
+        }
+
+        let slowest = timer.ngrams.slowest(10, 1);
+        let names: Vec<&str> = slowest.iter().map(|(ngram, _)| *ngram).collect();
+        assert_eq!(names, vec!["he", "the", "th"]);
+        assert!((slowest[1].1.average() - 0.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn mistakes_and_pauses_start_over() {
+        let mut timer = Timer::new();
+        timer.keystroke('a', 0.0, true);
+        timer.keystroke('x', 0.1, false);
+        timer.keystroke('b', 0.2, true);
+        timer.keystroke('c', 5.0, true);
+        assert!(timer.ngrams.slowest(10, 1).is_empty());
+    }
+
+    #[test]
+    fn merging_adds_counts() {
+        let mut total = Ngrams::new();
+        let mut test = Ngrams::new();
+        test.record("ab", 0.2);
+        test.record("ab", 0.4);
+        total.merge(&test);
+        total.merge(&test);
+
+        let slowest = total.slowest(10, 4);
+        assert_eq!(slowest.len(), 1);
+        assert!((slowest[0].1.average() - 0.3).abs() < 1e-9);
+        assert!(total.slowest(10, 5).is_empty());
+    }
+}
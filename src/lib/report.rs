@@ -0,0 +1,115 @@
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const LAST_REPORT_FILE: &str = "last.json";
+
+#[derive(Serialize, Deserialize)]
+pub struct Series {
+    pub time: Vec<f64>,
+    pub net_wpm: Vec<f64>,
+    pub gross_wpm: Vec<f64>,
+}
+
+// Everything needed to reproduce the results screen outside of the test
+#[derive(Serialize, Deserialize)]
+pub struct Report {
+    pub timestamp: u64,
+    pub mode: String,
+    pub word_list: String,
+    #[serde(default)]
+    pub seed: Option<u64>,
+    #[serde(default)]
+    pub challenge: Option<String>,
+    #[serde(default)]
+    pub result_hash: Option<String>,
+    pub duration: f64,
+    pub gross_wpm: f64,
+    pub net_wpm: f64,
+    pub accuracy: f64,
+    pub consistency: f64,
+    pub peak_burst: f64,
+    pub correct: usize,
+    pub incorrect: usize,
+    pub extra: usize,
+    pub missed: usize,
+    pub failure: Option<String>,
+    pub invalid: Option<String>,
+    // How many times the test was paused
+    #[serde(default)]
+    pub pauses: u32,
+    pub log: Series,
+}
+
+impl Report {
+    pub fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0)
+    }
+
+    pub fn date(&self) -> String {
+        let (year, month, day) = civil_date(self.timestamp);
+        format!("{:04}-{:02}-{:02}", year, month, day)
+    }
+
+    pub fn save_last(&self) -> Result<(), io::Error> {
+        let path = data_dir()?.join(LAST_REPORT_FILE);
+        fs::write(path, serde_json::to_string(self)?)
+    }
+
+    pub fn load_last() -> Result<Report, io::Error> {
+        let path = data_dir()?.join(LAST_REPORT_FILE);
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+}
+
+// Follows the XDG base directory spec, falling back to ~/.local/share
+pub fn data_dir() -> Result<PathBuf, io::Error> {
+    let base = match env::var_os("XDG_DATA_HOME") {
+        Some(dir) => PathBuf::from(dir),
+        None => match env::var_os("HOME") {
+            Some(home) => PathBuf::from(home).join(".local").join("share"),
+            None => {
+                return Err(io::Error::new(io::ErrorKind::NotFound,
+                                          "could not find a home directory"))
+            }
+        },
+    };
+
+    let dir = base.join("bananatype");
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+// Converts a unix timestamp to a (year, month, day) in UTC
+pub fn civil_date(timestamp: u64) -> (i64, u32, u32) {
+    let days = (timestamp / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524
+                       - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_timestamps_to_dates() {
+        assert_eq!(civil_date(0), (1970, 1, 1));
+        assert_eq!(civil_date(951_782_400), (2000, 2, 29));
+        assert_eq!(civil_date(1_735_689_599), (2024, 12, 31));
+    }
+}
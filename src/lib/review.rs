@@ -0,0 +1,93 @@
+// Lines up what was typed with the text it was meant to match, one word at a
+// time. The input fills the text one character at a time, spaces included, so
+// characters are paired by their offset in the text.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Edit {
+    Match(char),
+    Substitution(char, char),
+    // A character typed in place of the space after a word
+    Insertion(char),
+    // A character of the text that was not reached before the test ended
+    Pending(char),
+}
+
+// The edits for each word of the text up to the last one typed into, cutting
+// the input at the same offsets as the words: each word and the space after it
+// take their own length
+pub fn align_words(words: &[String], typed: &[char]) -> Vec<Vec<Edit>> {
+    let mut offset = 0;
+
+    words
+        .iter()
+        .map_while(|word| {
+            if offset >= typed.len() {
+                return None;
+            }
+
+            let mut edits: Vec<Edit> = word
+                .chars()
+                .enumerate()
+                .map(|(i, expected)| match typed.get(offset + i) {
+                    Some(actual) if *actual == expected => Edit::Match(expected),
+                    Some(actual) => Edit::Substitution(expected, *actual),
+                    None => Edit::Pending(expected),
+                })
+                .collect();
+
+            offset += edits.len();
+            if let Some(extra) = typed.get(offset).filter(|actual| **actual != ' ') {
+                edits.push(Edit::Insertion(*extra));
+            }
+            offset += 1;
+
+            Some(edits)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(text: &str) -> Vec<String> {
+        text.split(' ').map(str::to_string).collect()
+    }
+
+    fn chars(text: &str) -> Vec<char> {
+        text.chars().collect()
+    }
+
+    #[test]
+    fn aligns_each_kind_of_mistake() {
+        let edits = align_words(&words("cat dog"), &chars("cut dogx"));
+        assert_eq!(edits[0], [Edit::Match('c'), Edit::Substitution('a', 'u'), Edit::Match('t')]);
+        assert_eq!(edits[1], [Edit::Match('d'), Edit::Match('o'), Edit::Match('g'), Edit::Insertion('x')]);
+    }
+
+    #[test]
+    fn mistakes_do_not_shift_later_words() {
+        let edits = align_words(&words("hello world again more"), &chars("hellp world ag"));
+
+        assert_eq!(edits[0][4], Edit::Substitution('o', 'p'));
+        assert!(edits[1].iter().all(|edit| matches!(edit, Edit::Match(_))));
+        assert_eq!(edits[2][2..], [Edit::Pending('a'), Edit::Pending('i'), Edit::Pending('n')]);
+    }
+
+    #[test]
+    fn words_after_the_input_are_left_out() {
+        assert_eq!(align_words(&words("hello world again"), &chars("hello wo")).len(), 2);
+        assert_eq!(align_words(&words("hello world again"), &chars("hello ")).len(), 1);
+        assert!(align_words(&words("hello world"), &[]).is_empty());
+    }
+
+    #[test]
+    fn a_character_typed_for_a_space_stays_with_its_word() {
+        let edits = align_words(&words("hello world again"), &chars("helloxworld again"));
+
+        assert_eq!(edits[0].last(), Some(&Edit::Insertion('x')));
+        assert_eq!(edits[0].len(), 6);
+        for edits in &edits[1..] {
+            assert!(edits.iter().all(|edit| matches!(edit, Edit::Match(_))));
+        }
+    }
+}
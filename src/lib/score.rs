@@ -1,54 +1,192 @@
+// A way of turning the character counts into speed and accuracy. Sites
+// disagree on the formulas, so each profile follows one of them.
+pub trait Scoring {
+    fn name(&self) -> &'static str;
+    fn formula(&self) -> &'static str;
+    fn gross_wpm(&self, score: &Score, minutes: f64) -> f64;
+    fn net_wpm(&self, score: &Score, minutes: f64) -> f64;
+    fn accuracy(&self, score: &Score) -> f64;
+}
+
+// Counts every typed character for gross speed and takes a penalty of one
+// word per minute for each error left uncorrected
+pub struct Classic;
+
+impl Scoring for Classic {
+    fn name(&self) -> &'static str {
+        "classic"
+    }
+
+    fn formula(&self) -> &'static str {
+        "gross = typed / 5 / min, net = gross - uncorrected errors / min, \
+         accuracy = correct / (correct + all errors)"
+    }
+
+    fn gross_wpm(&self, score: &Score, minutes: f64) -> f64 {
+        (score.correct_characters + score.incorrect_characters) / 5.0 / minutes
+    }
+
+    fn net_wpm(&self, score: &Score, minutes: f64) -> f64 {
+        self.gross_wpm(score, minutes) - score.incorrect_characters / minutes
+    }
+
+    fn accuracy(&self, score: &Score) -> f64 {
+        percentage(score.correct_characters,
+                   score.correct_characters + score.total_incorrect_characters)
+    }
+}
+
+// Speed only counts correct characters, raw speed counts every keystroke
+// including the ones later deleted, and accuracy is per keystroke
+pub struct Monkeytype;
+
+impl Scoring for Monkeytype {
+    fn name(&self) -> &'static str {
+        "monkeytype"
+    }
+
+    fn formula(&self) -> &'static str {
+        "net = correct / 5 / min, gross = keystrokes / 5 / min, \
+         accuracy = correct keystrokes / keystrokes"
+    }
+
+    fn gross_wpm(&self, score: &Score, minutes: f64) -> f64 {
+        score.keystrokes() / 5.0 / minutes
+    }
+
+    fn net_wpm(&self, score: &Score, minutes: f64) -> f64 {
+        score.correct_characters / 5.0 / minutes
+    }
+
+    fn accuracy(&self, score: &Score) -> f64 {
+        percentage(score.correct_keystrokes, score.keystrokes())
+    }
+}
+
+// Speed only counts correct characters, and every mistake costs accuracy
+// against the length of the text typed, even once it is fixed
+pub struct Typeracer;
+
+impl Scoring for Typeracer {
+    fn name(&self) -> &'static str {
+        "typeracer"
+    }
+
+    fn formula(&self) -> &'static str {
+        "net = correct / 5 / min, gross = typed / 5 / min, \
+         accuracy = (typed - all errors) / typed"
+    }
+
+    fn gross_wpm(&self, score: &Score, minutes: f64) -> f64 {
+        score.total_characters() / 5.0 / minutes
+    }
+
+    fn net_wpm(&self, score: &Score, minutes: f64) -> f64 {
+        score.correct_characters / 5.0 / minutes
+    }
+
+    fn accuracy(&self, score: &Score) -> f64 {
+        let typed = score.total_characters();
+        percentage(typed - score.total_incorrect_characters, typed)
+    }
+}
+
+const PROFILES: [&dyn Scoring; 3] = [&Classic, &Monkeytype, &Typeracer];
+
+pub fn profile(name: &str) -> Option<&'static dyn Scoring> {
+    PROFILES.iter().cloned().find(|profile| profile.name() == name)
+}
+
+// A share of a total as a percentage, clamped to 0-100 and 100 when nothing
+// has been typed yet
+fn percentage(part: f64, total: f64) -> f64 {
+    if total <= 0.0 {
+        100.0
+    } else {
+        (part / total * 100.0).clamp(0.0, 100.0)
+    }
+}
+
 pub struct Score {
     correct_characters: f64,
     incorrect_characters: f64,
     total_incorrect_characters: f64,
+    correct_keystrokes: f64,
+    idle_seconds: f64,
+    scoring: &'static dyn Scoring,
 }
 
 impl Score {
-    pub fn new() -> Score {
+    pub fn new(scoring: &'static dyn Scoring) -> Score {
         Score {
             correct_characters: 0.0,
             incorrect_characters: 0.0,
             total_incorrect_characters: 0.0,
+            correct_keystrokes: 0.0,
+            idle_seconds: 0.0,
+            scoring,
         }
     }
 
     pub fn calculate_gross_wpm(&self, elapsed_seconds: f64) -> f64 {
-        if elapsed_seconds == 0.0 {
+        let active_seconds = self.active_seconds(elapsed_seconds);
+        if active_seconds <= 0.0 {
             0.0
         } else {
-            ((self.correct_characters + self.incorrect_characters) / 5.0)
-             / (elapsed_seconds / 60.0)
+            self.scoring.gross_wpm(self, active_seconds / 60.0).max(0.0)
         }
     }
 
     pub fn calculate_net_wpm(&self, elapsed_seconds: f64) -> f64 {
-        if elapsed_seconds == 0.0 {
+        let active_seconds = self.active_seconds(elapsed_seconds);
+        if active_seconds <= 0.0 {
             0.0
         } else {
-            let net_wpm = self.calculate_gross_wpm(elapsed_seconds)
-                - (self.incorrect_characters / (elapsed_seconds / 60.0));
-            if net_wpm > 0.0 { net_wpm }
-            else { 0.0 }
+            self.scoring.net_wpm(self, active_seconds / 60.0).max(0.0)
         }
     }
 
     pub fn calculate_accuracy(&self) -> f64 {
-        if self.correct_characters + self.total_incorrect_characters == 0.0 {
-            100.0
-        } else {
-            (self.correct_characters
-             / (self.correct_characters + self.total_incorrect_characters))
-            * 100.0
-        }
+        self.scoring.accuracy(self)
+    }
+
+    pub fn scoring(&self) -> &'static dyn Scoring {
+        self.scoring
+    }
+
+    // Idle periods are excluded from the time used for WPM
+    fn active_seconds(&self, elapsed_seconds: f64) -> f64 {
+        elapsed_seconds - self.idle_seconds
+    }
+
+    pub fn idle_seconds(&self) -> f64 {
+        self.idle_seconds
+    }
+
+    pub fn total_characters(&self) -> f64 {
+        self.correct_characters + self.incorrect_characters
+    }
+
+    pub fn total_incorrect_characters(&self) -> f64 {
+        self.total_incorrect_characters
+    }
+
+    // Every character typed, including the ones deleted afterwards
+    fn keystrokes(&self) -> f64 {
+        self.correct_keystrokes + self.total_incorrect_characters
+    }
+
+    pub fn calculate_idle(&mut self, seconds: f64) {
+        self.idle_seconds += seconds;
     }
 
     pub fn calculate_correct(&mut self) {
         self.correct_characters += 1.0;
+        self.correct_keystrokes += 1.0;
     }
 
     pub fn calculate_correct_backspace(&mut self) {
-        self.correct_characters -= 1.0;
+        self.correct_characters = (self.correct_characters - 1.0).max(0.0);
     }
 
     pub fn calculate_incorrect(&mut self) {
@@ -57,6 +195,194 @@ impl Score {
     }
 
     pub fn calculate_incorrect_backspace(&mut self) {
-        self.incorrect_characters -= 1.0;
+        self.incorrect_characters = (self.incorrect_characters - 1.0).max(0.0);
+    }
+}
+
+// Compares what was typed against the target text. Typing a non-space where a
+// space was expected is an extra character, and typing a space where a letter
+// was expected is a missed character.
+pub struct CharacterBreakdown {
+    pub correct: usize,
+    pub incorrect: usize,
+    pub extra: usize,
+    pub missed: usize,
+}
+
+impl CharacterBreakdown {
+    pub fn new<I: Iterator<Item = char>>(target: I, typed: &[char]) -> CharacterBreakdown {
+        let mut breakdown = CharacterBreakdown {
+            correct: 0,
+            incorrect: 0,
+            extra: 0,
+            missed: 0,
+        };
+
+        for (expected, actual) in target.zip(typed.iter()) {
+            match (expected, *actual) {
+                (expected, actual) if expected == actual => breakdown.correct += 1,
+                (' ', _) => breakdown.extra += 1,
+                (_, ' ') => breakdown.missed += 1,
+                _ => breakdown.incorrect += 1,
+            }
+        }
+
+        breakdown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn typed(scoring: &'static dyn Scoring, correct: usize, incorrect: usize) -> Score {
+        let mut score = Score::new(scoring);
+        for _ in 0..correct {
+            score.calculate_correct();
+        }
+        for _ in 0..incorrect {
+            score.calculate_incorrect();
+        }
+        score
+    }
+
+    #[test]
+    fn classic_formulas() {
+        let score = typed(&Classic, 50, 5);
+        assert_eq!(score.calculate_gross_wpm(60.0), 11.0);
+        assert_eq!(score.calculate_net_wpm(60.0), 6.0);
+        assert!((score.calculate_accuracy() - 50.0 / 55.0 * 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn monkeytype_counts_deleted_keystrokes() {
+        let mut score = typed(&Monkeytype, 10, 5);
+        for _ in 0..5 {
+            score.calculate_incorrect_backspace();
+        }
+        assert_eq!(score.calculate_net_wpm(60.0), 2.0);
+        assert_eq!(score.calculate_gross_wpm(60.0), 3.0);
+        assert!((score.calculate_accuracy() - 10.0 / 15.0 * 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn typeracer_accuracy_keeps_corrected_errors() {
+        let mut score = typed(&Typeracer, 0, 2);
+        score.calculate_incorrect_backspace();
+        score.calculate_incorrect_backspace();
+        for _ in 0..10 {
+            score.calculate_correct();
+        }
+        assert_eq!(score.calculate_accuracy(), 80.0);
+        assert_eq!(score.calculate_net_wpm(60.0), 2.0);
+    }
+
+    #[test]
+    fn zero_elapsed_time_scores_zero() {
+        for scoring in PROFILES.iter().cloned() {
+            let score = typed(scoring, 20, 3);
+            assert_eq!(score.calculate_gross_wpm(0.0), 0.0);
+            assert_eq!(score.calculate_net_wpm(0.0), 0.0);
+        }
+    }
+
+    #[test]
+    fn idle_time_is_not_active_time() {
+        let mut score = typed(&Classic, 50, 0);
+        score.calculate_idle(30.0);
+        assert_eq!(score.calculate_gross_wpm(30.0), 0.0);
+        assert_eq!(score.calculate_gross_wpm(90.0), 10.0);
+    }
+
+    #[test]
+    fn nothing_typed_is_full_accuracy() {
+        for scoring in PROFILES.iter().cloned() {
+            assert_eq!(Score::new(scoring).calculate_accuracy(), 100.0);
+        }
+    }
+
+    #[test]
+    fn all_errors() {
+        for scoring in PROFILES.iter().cloned() {
+            let score = typed(scoring, 0, 25);
+            assert_eq!(score.calculate_accuracy(), 0.0);
+            assert_eq!(score.calculate_net_wpm(60.0), 0.0);
+        }
+    }
+
+    #[test]
+    fn backspacing_past_corrections_stays_at_zero() {
+        let mut score = typed(&Classic, 2, 1);
+        for _ in 0..5 {
+            score.calculate_correct_backspace();
+            score.calculate_incorrect_backspace();
+        }
+        assert_eq!(score.total_characters(), 0.0);
+        assert_eq!(score.calculate_gross_wpm(60.0), 0.0);
+        assert_eq!(score.total_incorrect_characters(), 1.0);
+    }
+
+    #[test]
+    fn profiles_by_name() {
+        for scoring in PROFILES.iter() {
+            assert_eq!(profile(scoring.name()).map(|found| found.name()), Some(scoring.name()));
+        }
+        assert!(profile("unknown").is_none());
+    }
+
+    #[test]
+    fn character_breakdown() {
+        let typed: Vec<char> = "ab dxe".chars().collect();
+        let breakdown = CharacterBreakdown::new("abc de".chars(), &typed);
+        assert_eq!(breakdown.correct, 3);
+        assert_eq!(breakdown.missed, 1);
+        assert_eq!(breakdown.extra, 1);
+        assert_eq!(breakdown.incorrect, 1);
+    }
+
+    #[derive(Clone, Debug)]
+    enum Key {
+        Correct,
+        Incorrect,
+        CorrectBackspace,
+        IncorrectBackspace,
+    }
+
+    fn key() -> impl Strategy<Value = Key> {
+        prop_oneof![
+            Just(Key::Correct),
+            Just(Key::Incorrect),
+            Just(Key::CorrectBackspace),
+            Just(Key::IncorrectBackspace),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn scores_stay_in_range(keys in prop::collection::vec(key(), 0..200),
+                                profile in 0..PROFILES.len(),
+                                elapsed in 0.0..600.0f64,
+                                idle in 0.0..600.0f64) {
+            let mut score = Score::new(PROFILES[profile]);
+            for key in keys {
+                match key {
+                    Key::Correct => score.calculate_correct(),
+                    Key::Incorrect => score.calculate_incorrect(),
+                    Key::CorrectBackspace => score.calculate_correct_backspace(),
+                    Key::IncorrectBackspace => score.calculate_incorrect_backspace(),
+                }
+            }
+            score.calculate_idle(idle);
+
+            let accuracy = score.calculate_accuracy();
+            let gross = score.calculate_gross_wpm(elapsed);
+            let net = score.calculate_net_wpm(elapsed);
+            prop_assert!((0.0..=100.0).contains(&accuracy));
+            prop_assert!(gross >= 0.0);
+            prop_assert!(net >= 0.0);
+            prop_assert!(score.total_characters() >= 0.0);
+            prop_assert!(net <= gross + 1e-9);
+        }
     }
 }
@@ -0,0 +1,332 @@
+use super::challenge::Challenge;
+use super::keymap::{Action, KeyBinding, Keymap};
+use super::score::{self, Scoring};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+// Options that take no value on the command line
+const FLAGS: [&str; 5] = ["sudden-death", "caret-blink", "lesson", "no-repeats", "zen"];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Caret {
+    Block,
+    Underline,
+    Bar,
+    Off,
+}
+
+impl Caret {
+    fn from_name(name: &str) -> Option<Caret> {
+        match name {
+            "block" => Some(Caret::Block),
+            "underline" => Some(Caret::Underline),
+            "bar" => Some(Caret::Bar),
+            "off" => Some(Caret::Off),
+            _ => None,
+        }
+    }
+}
+
+// Where the words of a test come from
+#[derive(Clone, PartialEq)]
+pub enum WordSource {
+    English,
+    // The English words, common ones more often
+    Frequency,
+    // Words from a file given by the user
+    File(Vec<String>),
+    Quotes,
+    Code,
+    // A text given by the user, typed as is
+    Custom(String),
+    Markov,
+}
+
+impl WordSource {
+    fn from_name(name: &str) -> Option<WordSource> {
+        match name {
+            "english" => Some(WordSource::English),
+            "frequency" => Some(WordSource::Frequency),
+            "quotes" => Some(WordSource::Quotes),
+            "code" => Some(WordSource::Code),
+            "markov" => Some(WordSource::Markov),
+            _ => None,
+        }
+    }
+
+    // Whether words are drawn at random rather than typed in a set order
+    pub fn is_random(&self) -> bool {
+        matches!(self, WordSource::English | WordSource::Frequency | WordSource::File(_) | WordSource::Markov)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            WordSource::English => "english",
+            WordSource::Frequency => "frequency",
+            WordSource::File(_) => "file",
+            WordSource::Quotes => "quotes",
+            WordSource::Code => "code",
+            WordSource::Custom(_) => "custom",
+            WordSource::Markov => "markov",
+        }
+    }
+}
+
+pub struct Settings {
+    pub duration: f64,
+    pub seed: Option<u64>,
+    pub sudden_death: bool,
+    pub min_accuracy: Option<f64>,
+    pub min_wpm: Option<f64>,
+    pub target_wpm: Option<f64>,
+    pub min_wpm_window: f64,
+    pub fail_grace_period: f64,
+    pub visible_lines: usize,
+    pub caret: Caret,
+    pub caret_blink: bool,
+    pub pace_caret: Option<f64>,
+    pub scoring: &'static dyn Scoring,
+    pub lesson: bool,
+    // Free typing with no text and no time limit
+    pub zen: bool,
+    pub lesson_wpm: f64,
+    pub lesson_accuracy: f64,
+    pub words: WordSource,
+    // Text the Markov model learns from instead of the English word list
+    pub corpus: Option<String>,
+    pub markov_order: usize,
+    pub min_length: Option<usize>,
+    pub max_length: Option<usize>,
+    pub letters: Option<String>,
+    pub top: Option<usize>,
+    pub must_contain: Option<String>,
+    pub no_repeats: bool,
+    pub keymap: Keymap,
+}
+
+impl Settings {
+    pub fn new() -> Settings {
+        Settings {
+            duration: 30.0,
+            seed: None,
+            sudden_death: false,
+            min_accuracy: None,
+            min_wpm: None,
+            target_wpm: None,
+            min_wpm_window: 3.0,
+            fail_grace_period: 5.0,
+            visible_lines: 3,
+            caret: Caret::Block,
+            caret_blink: false,
+            pace_caret: None,
+            scoring: &score::Classic,
+            lesson: false,
+            zen: false,
+            lesson_wpm: 30.0,
+            lesson_accuracy: 95.0,
+            words: WordSource::English,
+            corpus: None,
+            markov_order: 2,
+            min_length: None,
+            max_length: None,
+            letters: None,
+            top: None,
+            must_contain: None,
+            no_repeats: false,
+            keymap: Keymap::new(),
+        }
+    }
+
+    // Reads the config file, then lets command line arguments override it
+    pub fn from_args<I: Iterator<Item = String>>(mut args: I) -> Result<Settings, String> {
+        let mut settings = Settings::from_config()?;
+
+        while let Some(arg) = args.next() {
+            let name = arg
+                .strip_prefix("--")
+                .ok_or(format!("unknown argument '{}'", arg))?;
+
+            let value = if FLAGS.contains(&name) {
+                "true".to_string()
+            } else {
+                args.next().ok_or(format!("missing value for '{}'", arg))?
+            };
+
+            settings.set(name, &value)?;
+        }
+
+        Ok(settings)
+    }
+
+    // The config file holds `name = value` lines using the command line option
+    // names, plus `key.<action> = <keys>` lines to rebind actions
+    fn from_config() -> Result<Settings, String> {
+        let mut settings = Settings::new();
+
+        let path = match Settings::config_path() {
+            Some(path) => path,
+            None => return Ok(settings),
+        };
+
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(settings),
+            Err(error) => return Err(format!("could not read '{}': {}", path.display(), error)),
+        };
+
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (name, value) = line
+                .split_once('=')
+                .ok_or(format!("{}:{}: expected 'name = value'", path.display(), number + 1))?;
+
+            settings
+                .set(name.trim(), value.trim())
+                .map_err(|message| format!("{}:{}: {}", path.display(), number + 1, message))?;
+        }
+
+        Ok(settings)
+    }
+
+    fn config_path() -> Option<PathBuf> {
+        let base = match env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+        };
+        Some(base.join("bananatype").join("config"))
+    }
+
+    fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        if let Some(action) = name.strip_prefix("key.") {
+            let action = Action::from_name(action)
+                .ok_or(format!("unknown action '{}'", action))?;
+            let keys = value
+                .split(',')
+                .map(|key| KeyBinding::parse(key).ok_or(format!("invalid key '{}'", key.trim())))
+                .collect::<Result<Vec<KeyBinding>, String>>()?;
+            self.keymap.bind(action, keys);
+            return Ok(());
+        }
+
+        match name {
+            "duration" => {
+                self.duration = Settings::parse_value(name, value)?;
+                if self.duration <= 0.0 {
+                    return Err(format!("invalid value '{}' for '{}'", value, name));
+                }
+            }
+            "seed" => self.seed = Some(Settings::parse_value(name, value)?),
+            "sudden-death" => self.sudden_death = Settings::parse_value(name, value)?,
+            "min-accuracy" => self.min_accuracy = Some(Settings::parse_value(name, value)?),
+            "min-wpm" => self.min_wpm = Some(Settings::parse_value(name, value)?),
+            "target-wpm" => self.target_wpm = Some(Settings::parse_value(name, value)?),
+            "challenge" => Challenge::parse(value)?.apply(self),
+            "min-wpm-window" => self.min_wpm_window = Settings::parse_value(name, value)?,
+            "lines" => {
+                self.visible_lines = Settings::parse_value(name, value)?;
+                if self.visible_lines == 0 {
+                    return Err(format!("invalid value '{}' for '{}'", value, name));
+                }
+            }
+            "caret" => {
+                self.caret = Caret::from_name(value)
+                    .ok_or(format!("invalid value '{}' for '{}'", value, name))?;
+            }
+            "caret-blink" => self.caret_blink = Settings::parse_value(name, value)?,
+            "pace-caret" => self.pace_caret = Some(Settings::parse_value(name, value)?),
+            "scoring" => {
+                self.scoring = score::profile(value)
+                    .ok_or(format!("invalid value '{}' for '{}'", value, name))?;
+            }
+            "lesson" => self.lesson = Settings::parse_value(name, value)?,
+            "zen" => self.zen = Settings::parse_value(name, value)?,
+            "lesson-wpm" => self.lesson_wpm = Settings::parse_value(name, value)?,
+            "lesson-accuracy" => self.lesson_accuracy = Settings::parse_value(name, value)?,
+            "words" => {
+                self.words = WordSource::from_name(value)
+                    .ok_or(format!("invalid value '{}' for '{}'", value, name))?;
+            }
+            "word-file" => {
+                let contents = fs::read_to_string(value)
+                    .map_err(|error| format!("could not read '{}': {}", value, error))?;
+                let words: Vec<String> = contents.split_whitespace().map(str::to_string).collect();
+                if words.is_empty() {
+                    return Err(format!("'{}' has no words", value));
+                }
+                self.words = WordSource::File(words);
+            }
+            "text" => {
+                if value.trim().is_empty() {
+                    return Err(format!("invalid value '{}' for '{}'", value, name));
+                }
+                self.words = WordSource::Custom(value.to_string());
+            }
+            "text-file" => {
+                let contents = fs::read_to_string(value)
+                    .map_err(|error| format!("could not read '{}': {}", value, error))?;
+                if contents.trim().is_empty() {
+                    return Err(format!("'{}' has no words", value));
+                }
+                self.words = WordSource::Custom(contents);
+            }
+            "corpus" => {
+                let contents = fs::read_to_string(value)
+                    .map_err(|error| format!("could not read '{}': {}", value, error))?;
+                self.corpus = Some(contents);
+            }
+            "markov-order" => {
+                self.markov_order = Settings::parse_value(name, value)?;
+                if self.markov_order == 0 {
+                    return Err(format!("invalid value '{}' for '{}'", value, name));
+                }
+            }
+            "min-length" => self.min_length = Some(Settings::parse_value(name, value)?),
+            "max-length" => self.max_length = Some(Settings::parse_value(name, value)?),
+            "letters" => {
+                if value.is_empty() || !value.chars().all(char::is_alphabetic) {
+                    return Err(format!("invalid value '{}' for '{}'", value, name));
+                }
+                self.letters = Some(value.to_lowercase());
+            }
+            "top" => {
+                let top: usize = Settings::parse_value(name, value)?;
+                if top == 0 {
+                    return Err(format!("invalid value '{}' for '{}'", value, name));
+                }
+                self.top = Some(top);
+            }
+            "must-contain" => {
+                if value.is_empty() {
+                    return Err(format!("invalid value '{}' for '{}'", value, name));
+                }
+                self.must_contain = Some(value.to_string());
+            }
+            "no-repeats" => self.no_repeats = Settings::parse_value(name, value)?,
+            "grace-period" => self.fail_grace_period = Settings::parse_value(name, value)?,
+            _ => return Err(format!("unknown option '{}'", name)),
+        }
+
+        Ok(())
+    }
+
+    // Whether any option leaves words out of the word source
+    pub fn filters_words(&self) -> bool {
+        self.top.is_some()
+            || self.min_length.is_some()
+            || self.max_length.is_some()
+            || self.must_contain.is_some()
+            || self.no_repeats
+    }
+
+    fn parse_value<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+        value
+            .parse()
+            .map_err(|_| format!("invalid value '{}' for '{}'", value, name))
+    }
+}
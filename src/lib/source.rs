@@ -0,0 +1,344 @@
+use super::markov::{Constraints, Markov};
+use super::settings::{Settings, WordSource};
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::{Rng, RngCore};
+use std::collections::{HashSet, VecDeque};
+
+const ENGLISH: &str = include_str!("words.txt");
+// Quotes are one per line, code snippets are separated by blank lines
+const QUOTES: &str = include_str!("quotes.txt");
+const CODE: &str = include_str!("code.txt");
+// Filters give up on a source after rejecting this many tokens in a row
+const MAX_REJECTIONS: usize = 1000;
+// Lengths of pseudo-words unless set with `--min-length` and `--max-length`
+const MARKOV_MIN_LENGTH: usize = 2;
+const MARKOV_MAX_LENGTH: usize = 8;
+
+// Hands out the words of a test one at a time, so sources can be endless and
+// filters only pull as many tokens as they need. Every source draws from the
+// test's seeded generator so the same seed gives the same text.
+pub trait TextSource {
+    // The next token, or `None` once the source has run out
+    fn next_token(&mut self, rng: &mut dyn RngCore) -> Option<String>;
+
+    // The tokens that must follow once a test has enough, so nothing started
+    // is cut short: the end of the current passage, or all of a given text
+    fn remainder(&mut self, _rng: &mut dyn RngCore) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn tokens(&mut self, rng: &mut dyn RngCore, count: usize) -> Vec<String> {
+        (0..count).map_while(|_| self.next_token(rng)).collect()
+    }
+
+    // At least `count` tokens, followed by the remainder
+    fn text(&mut self, rng: &mut dyn RngCore, count: usize) -> Vec<String> {
+        let mut tokens = self.tokens(rng, count);
+        tokens.extend(self.remainder(rng));
+        tokens
+    }
+}
+
+// Words picked uniformly from a list, such as the embedded English words
+pub struct WordList {
+    words: Vec<String>,
+}
+
+impl WordList {
+    pub fn new(words: Vec<String>) -> WordList {
+        WordList { words }
+    }
+}
+
+impl TextSource for WordList {
+    fn next_token(&mut self, rng: &mut dyn RngCore) -> Option<String> {
+        if self.words.is_empty() {
+            return None;
+        }
+        Some(self.words[rng.gen_range(0..self.words.len())].clone())
+    }
+}
+
+// Words from a list ordered from most to least common, picked as often as
+// Zipf's law says they appear in real text
+pub struct Weighted {
+    words: Vec<String>,
+    weights: WeightedIndex<f64>,
+}
+
+impl Weighted {
+    pub fn new(words: Vec<String>) -> Option<Weighted> {
+        let weights = WeightedIndex::new((1..=words.len()).map(|rank| 1.0 / rank as f64)).ok()?;
+        Some(Weighted { words, weights })
+    }
+}
+
+impl TextSource for Weighted {
+    fn next_token(&mut self, rng: &mut dyn RngCore) -> Option<String> {
+        Some(self.words[self.weights.sample(rng)].clone())
+    }
+}
+
+// Whole passages picked at random, like quotes or code snippets, handed out
+// word by word so each passage is typed in order
+pub struct Passages {
+    passages: Vec<String>,
+    current: VecDeque<String>,
+}
+
+impl Passages {
+    pub fn new(passages: Vec<String>) -> Passages {
+        Passages {
+            passages,
+            current: VecDeque::new(),
+        }
+    }
+
+    pub fn quotes() -> Passages {
+        Passages::new(QUOTES.lines().map(str::to_string).collect())
+    }
+
+    pub fn code() -> Passages {
+        Passages::new(CODE.split("\n\n").map(str::to_string).collect())
+    }
+}
+
+impl TextSource for Passages {
+    fn next_token(&mut self, rng: &mut dyn RngCore) -> Option<String> {
+        while self.current.is_empty() {
+            if self.passages.is_empty() {
+                return None;
+            }
+            let passage = &self.passages[rng.gen_range(0..self.passages.len())];
+            self.current = passage.split_whitespace().map(str::to_string).collect();
+        }
+        self.current.pop_front()
+    }
+
+    fn remainder(&mut self, _rng: &mut dyn RngCore) -> Vec<String> {
+        self.current.drain(..).collect()
+    }
+}
+
+// A text given by the user, typed once from start to end
+pub struct Custom {
+    words: VecDeque<String>,
+}
+
+impl Custom {
+    pub fn new(text: &str) -> Custom {
+        Custom {
+            words: text.split_whitespace().map(str::to_string).collect(),
+        }
+    }
+}
+
+impl TextSource for Custom {
+    fn next_token(&mut self, _rng: &mut dyn RngCore) -> Option<String> {
+        self.words.pop_front()
+    }
+
+    fn remainder(&mut self, _rng: &mut dyn RngCore) -> Vec<String> {
+        self.words.drain(..).collect()
+    }
+}
+
+pub struct PseudoWords {
+    markov: Markov,
+    min_length: usize,
+    max_length: usize,
+    letters: Option<String>,
+}
+
+impl PseudoWords {
+    pub fn new(markov: Markov, min_length: usize, max_length: usize, letters: Option<String>) -> PseudoWords {
+        PseudoWords {
+            markov,
+            min_length,
+            max_length,
+            letters,
+        }
+    }
+}
+
+impl TextSource for PseudoWords {
+    fn next_token(&mut self, mut rng: &mut dyn RngCore) -> Option<String> {
+        let constraints = Constraints {
+            min_length: self.min_length,
+            max_length: self.max_length,
+            letters: self.letters.as_deref(),
+        };
+        self.markov.generate(&mut rng, &constraints)
+    }
+}
+
+// Wraps a source to skip the tokens `keep` rejects
+pub struct Filter<F: FnMut(&str) -> bool> {
+    source: Box<dyn TextSource>,
+    keep: F,
+}
+
+impl<F: FnMut(&str) -> bool> TextSource for Filter<F> {
+    fn next_token(&mut self, rng: &mut dyn RngCore) -> Option<String> {
+        for _ in 0..MAX_REJECTIONS {
+            let token = self.source.next_token(rng)?;
+            if (self.keep)(&token) {
+                return Some(token);
+            }
+        }
+        None
+    }
+
+    fn remainder(&mut self, rng: &mut dyn RngCore) -> Vec<String> {
+        let keep = &mut self.keep;
+        self.source.remainder(rng).into_iter().filter(|token| keep(token)).collect()
+    }
+}
+
+pub fn filter<F: FnMut(&str) -> bool + 'static>(source: Box<dyn TextSource>, keep: F) -> Box<dyn TextSource> {
+    Box::new(Filter { source, keep })
+}
+
+pub fn length(source: Box<dyn TextSource>, min: usize, max: usize) -> Box<dyn TextSource> {
+    filter(source, move |token| (min..=max).contains(&token.chars().count()))
+}
+
+// Only tokens with at least one of the letters, to drill weak keys
+pub fn must_contain(source: Box<dyn TextSource>, letters: String) -> Box<dyn TextSource> {
+    filter(source, move |token| token.chars().any(|c| letters.contains(c)))
+}
+
+// No token twice in a row, so random words never give "the the"
+pub fn no_immediate_repeats(source: Box<dyn TextSource>) -> Box<dyn TextSource> {
+    let mut last = String::new();
+    filter(source, move |token| {
+        if token == last {
+            return false;
+        }
+        last = token.to_string();
+        true
+    })
+}
+
+// Every token at most once
+pub fn no_repeats(source: Box<dyn TextSource>) -> Box<dyn TextSource> {
+    let mut seen = HashSet::new();
+    filter(source, move |token| seen.insert(token.to_string()))
+}
+
+// Learns from the corpus when one was given, otherwise from the English words
+pub fn markov(settings: &Settings) -> Markov {
+    match &settings.corpus {
+        Some(corpus) => Markov::train(corpus.split(|c: char| !c.is_alphabetic()), settings.markov_order),
+        None => Markov::train(ENGLISH.lines(), settings.markov_order),
+    }
+}
+
+// The source chosen in the settings, wrapped in the filters that are set
+pub fn from_settings(settings: &Settings) -> Box<dyn TextSource> {
+    // Word lists are ordered from most to least common, so the top words are
+    // the first ones
+    let top = |words: Vec<String>| -> Vec<String> {
+        let count = settings.top.unwrap_or(words.len());
+        words.into_iter().take(count).collect()
+    };
+    let english = || top(ENGLISH.lines().map(str::to_string).collect());
+
+    let mut source: Box<dyn TextSource> = match &settings.words {
+        WordSource::English => Box::new(WordList::new(english())),
+        WordSource::Frequency => Box::new(Weighted::new(english()).expect("the word list is empty")),
+        WordSource::File(words) => Box::new(WordList::new(top(words.clone()))),
+        WordSource::Quotes => Box::new(Passages::quotes()),
+        WordSource::Code => Box::new(Passages::code()),
+        WordSource::Custom(text) => Box::new(Custom::new(text)),
+        WordSource::Markov => Box::new(PseudoWords::new(
+            markov(settings),
+            settings.min_length.unwrap_or(MARKOV_MIN_LENGTH),
+            settings.max_length.unwrap_or(MARKOV_MAX_LENGTH),
+            settings.letters.clone(),
+        )),
+    };
+
+    // Pseudo-words already keep to the length limits
+    if settings.words != WordSource::Markov
+        && (settings.min_length.is_some() || settings.max_length.is_some())
+    {
+        source = length(source,
+                        settings.min_length.unwrap_or(0),
+                        settings.max_length.unwrap_or(usize::MAX));
+    }
+    if let Some(letters) = &settings.must_contain {
+        source = must_contain(source, letters.clone());
+    }
+    if settings.no_repeats {
+        source = no_repeats(source);
+    }
+    // Words drawn at random should not double up, but a text given in full
+    // keeps its own words
+    if settings.words.is_random() {
+        source = no_immediate_repeats(source);
+    }
+
+    source
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn passages_are_typed_in_order() {
+        let mut source = Passages::new(vec!["to be or not to be".to_string()]);
+        let tokens = source.tokens(&mut ChaCha8Rng::seed_from_u64(1), 8);
+        assert_eq!(tokens, ["to", "be", "or", "not", "to", "be", "to", "be"]);
+    }
+
+    #[test]
+    fn custom_text_runs_out() {
+        let tokens = Custom::new("just  three\nwords").tokens(&mut ChaCha8Rng::seed_from_u64(1), 10);
+        assert_eq!(tokens, ["just", "three", "words"]);
+    }
+
+    #[test]
+    fn finite_sources_are_not_cut_short() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let mut source = Passages::new(vec!["to be or not to be".to_string()]);
+        assert_eq!(source.text(&mut rng, 8),
+                   ["to", "be", "or", "not", "to", "be", "to", "be", "or", "not", "to", "be"]);
+
+        let text = "word ".repeat(150);
+        assert_eq!(Custom::new(&text).text(&mut rng, 100).len(), 150);
+    }
+
+    #[test]
+    fn filters_compose() {
+        let source = Box::new(WordList::new(ENGLISH.lines().map(str::to_string).collect()));
+        let mut source = no_repeats(must_contain(length(source, 4, 5), "qz".to_string()));
+        let tokens = source.tokens(&mut ChaCha8Rng::seed_from_u64(2), 1000);
+
+        assert!(!tokens.is_empty() && tokens.len() < 1000);
+        assert!(tokens.iter().all(|token| (4..=5).contains(&token.len()) && token.contains(['q', 'z'])));
+        assert_eq!(tokens.iter().collect::<HashSet<_>>().len(), tokens.len());
+    }
+
+    #[test]
+    fn random_words_never_double_up() {
+        let mut settings = Settings::new();
+        settings.top = Some(2);
+        let tokens = from_settings(&settings).tokens(&mut ChaCha8Rng::seed_from_u64(4), 100);
+
+        assert_eq!(tokens.len(), 100);
+        assert!(tokens.iter().all(|token| token == "the" || token == "of"));
+        assert!(tokens.windows(2).all(|pair| pair[0] != pair[1]));
+    }
+
+    #[test]
+    fn common_words_come_up_more_often() {
+        let words: Vec<String> = ["the", "of", "rare"].iter().map(|word| word.to_string()).collect();
+        let tokens = Weighted::new(words).unwrap().tokens(&mut ChaCha8Rng::seed_from_u64(3), 1000);
+        let count = |word: &str| tokens.iter().filter(|token| *token == word).count();
+        assert!(count("the") > count("of") && count("of") > count("rare"));
+    }
+}
@@ -0,0 +1,158 @@
+use super::report::Report;
+
+pub const HEATMAP_WEEKS: usize = 52;
+const SECONDS_PER_DAY: u64 = 86_400;
+
+pub struct Filter {
+    pub mode: Option<String>,
+    pub word_list: Option<String>,
+}
+
+impl Filter {
+    pub fn new() -> Filter {
+        Filter {
+            mode: None,
+            word_list: None,
+        }
+    }
+
+    pub fn matches(&self, report: &Report) -> bool {
+        self.mode.as_ref().is_none_or(|mode| *mode == report.mode)
+            && self.word_list.as_ref().is_none_or(|list| *list == report.word_list)
+    }
+
+    pub fn describe(&self) -> String {
+        format!("mode: {}, words: {}",
+                self.mode.as_deref().unwrap_or("all"),
+                self.word_list.as_deref().unwrap_or("all"))
+    }
+}
+
+// Steps an optional filter value through None followed by every option in turn
+pub fn cycle(current: &Option<String>, options: &[String]) -> Option<String> {
+    match current {
+        None => options.first().cloned(),
+        Some(value) => options
+            .iter()
+            .position(|option| option == value)
+            .and_then(|index| options.get(index + 1).cloned()),
+    }
+}
+
+pub fn distinct<F: Fn(&Report) -> &str>(reports: &[Report], field: F) -> Vec<String> {
+    let mut values: Vec<String> = reports.iter().map(|report| field(report).to_string()).collect();
+    values.sort();
+    values.dedup();
+    values
+}
+
+// Averages each value with up to `window - 1` values before it
+pub fn rolling_average(values: &[f64], window: usize) -> Vec<f64> {
+    (0..values.len())
+        .map(|i| {
+            let start = (i + 1).saturating_sub(window);
+            values[start..=i].iter().sum::<f64>() / (i + 1 - start) as f64
+        })
+        .collect()
+}
+
+pub fn total_seconds(reports: &[&Report]) -> f64 {
+    reports.iter().map(|report| report.duration).sum()
+}
+
+pub fn format_duration(seconds: f64) -> String {
+    let seconds = seconds.round() as u64;
+    format!("{}h {:02}m {:02}s", seconds / 3600, (seconds / 60) % 60, seconds % 60)
+}
+
+// Tests per day for the last HEATMAP_WEEKS weeks, one column per week with
+// Monday first. Days after `now` are left at zero.
+pub fn daily_activity(reports: &[&Report], now: u64) -> Vec<[u32; 7]> {
+    let today = now / SECONDS_PER_DAY;
+    // The unix epoch fell on a Thursday
+    let weekday = (today + 3) % 7;
+    let first_day = (today - weekday).saturating_sub(7 * (HEATMAP_WEEKS as u64 - 1));
+
+    let mut weeks = vec![[0; 7]; HEATMAP_WEEKS];
+    for report in reports {
+        let day = report.timestamp / SECONDS_PER_DAY;
+        if day >= first_day && day <= today {
+            let offset = (day - first_day) as usize;
+            weeks[offset / 7][offset % 7] += 1;
+        }
+    }
+    weeks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::report::Series;
+
+    fn report(timestamp: u64) -> Report {
+        Report {
+            timestamp,
+            mode: "time 30".to_string(),
+            word_list: "english".to_string(),
+            seed: None,
+            challenge: None,
+            result_hash: None,
+            duration: 30.0,
+            gross_wpm: 0.0,
+            net_wpm: 0.0,
+            accuracy: 0.0,
+            consistency: 0.0,
+            peak_burst: 0.0,
+            correct: 0,
+            incorrect: 0,
+            extra: 0,
+            missed: 0,
+            failure: None,
+            invalid: None,
+            pauses: 0,
+            log: Series {
+                time: Vec::new(),
+                net_wpm: Vec::new(),
+                gross_wpm: Vec::new(),
+            },
+        }
+    }
+
+    #[test]
+    fn cycles_through_none_and_every_option() {
+        let options = vec!["a".to_string(), "b".to_string()];
+        let mut value = None;
+        let mut seen = Vec::new();
+        for _ in 0..3 {
+            value = cycle(&value, &options);
+            seen.push(value.clone());
+        }
+        assert_eq!(seen, [Some("a".to_string()), Some("b".to_string()), None]);
+        assert_eq!(cycle(&None, &[]), None);
+    }
+
+    #[test]
+    fn rolling_average_uses_the_values_so_far() {
+        assert_eq!(rolling_average(&[2.0, 4.0, 6.0, 8.0], 2), [2.0, 3.0, 5.0, 7.0]);
+        assert_eq!(rolling_average(&[1.0, 2.0, 3.0], 10), [1.0, 1.5, 2.0]);
+        assert!(rolling_average(&[], 5).is_empty());
+    }
+
+    #[test]
+    fn activity_is_counted_by_week_and_weekday() {
+        // Wednesday, 3 January 2024
+        let now = 1_704_240_000;
+        let reports: Vec<Report> = [now, now - SECONDS_PER_DAY, now - 2 * SECONDS_PER_DAY,
+                                    now - 2 * SECONDS_PER_DAY, now - 9 * SECONDS_PER_DAY,
+                                    now - 400 * SECONDS_PER_DAY, now + SECONDS_PER_DAY]
+            .iter()
+            .map(|timestamp| report(*timestamp))
+            .collect();
+        let weeks = daily_activity(&reports.iter().collect::<Vec<_>>(), now);
+
+        assert_eq!(weeks.len(), HEATMAP_WEEKS);
+        assert_eq!(weeks[HEATMAP_WEEKS - 1], [2, 1, 1, 0, 0, 0, 0]);
+        assert_eq!(weeks[HEATMAP_WEEKS - 2], [1, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(weeks.iter().flatten().sum::<u32>(), 5);
+    }
+}
@@ -1,10 +1,8 @@
+#![allow(special_module_name)]
+
 // TODO: Add theme config file
 // TODO: Change text source file directory
-// TODO: Display menu where user can choose test duration
 // TODO: Add more words when all words are typed
-// TODO: Check if any words were typed or if person is afk
-// TODO: Add line graph at summary page to get statistics
-// TODO: Move final stas (net wpm, gross wpm and accuracy to 3 columns)
 // TODO: simulate Ctrl+PLUS to increase font size of terminal
 
 /* For MacOS
@@ -21,12 +19,50 @@ tell application "Terminal"
 end tell
 */
 
+use std::env;
 use std::io;
+use std::process;
 
 mod lib;
 
 fn main() -> Result<(), io::Error> {
-    let mut test = lib::TypingTest::new();
-    test.start_test();
-    Ok(())
+    let mut args = env::args().skip(1).peekable();
+
+    if args.peek().map(String::as_str) == Some("export") {
+        args.next();
+        if let Err(message) = lib::export(args) {
+            eprintln!("bananatype: {}", message);
+            process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if args.peek().map(String::as_str) == Some("verify") {
+        args.next();
+        if let Err(message) = lib::verify(args) {
+            eprintln!("bananatype: {}", message);
+            process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let stats = args.peek().map(String::as_str) == Some("stats");
+    if stats {
+        args.next();
+    }
+
+    let settings = match lib::Settings::from_args(args) {
+        Ok(settings) => settings,
+        Err(message) => {
+            eprintln!("bananatype: {}", message);
+            process::exit(2);
+        }
+    };
+
+    let mut test = lib::TypingTest::new(settings);
+    if stats {
+        test.start_stats()
+    } else {
+        test.start_test()
+    }
 }